@@ -1,4 +1,7 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
     frequencies::FREQUENCIES,
@@ -8,6 +11,9 @@ use crate::{
     waves::wave,
 };
 
+// bytes an audio takes up in memory: the speed and 3 bytes per item
+pub static AUDIO_SIZE: u32 = 97;
+
 static mut __VOL: u8 = 100;
 static mut __IS_MUTED: bool = false;
 
@@ -59,7 +65,7 @@ impl WaveType {
             4 => Self::TiltedSawtoothWave,
             5 => Self::NoiseWave,
             6 => Self::OrganWave,
-            0 | _ => Self::SquareWave,
+            _ => Self::SquareWave,
         }
     }
 }
@@ -99,6 +105,12 @@ impl AudioItem {
     }
 }
 
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Audio {
     pub fn write_to_memory(&self, memory: &MemorySection, mut offset: u32) {
         memory.set_at_addr(offset, self.speed);
@@ -116,7 +128,33 @@ impl Audio {
         }
     }
 
-    fn from_memory(memory: &MemorySection, mut offset: u32) -> Self {
+    // the byte at the offset when the audio is written to memory
+    pub fn get_byte(&self, offset: u32) -> u8 {
+        if offset == 0 {
+            return self.speed;
+        }
+        let item = &self.items[((offset - 1) / 3) as usize % 32];
+        match (offset - 1) % 3 {
+            0 => item.wave_type as u8,
+            1 => item.sound,
+            _ => item.volume,
+        }
+    }
+
+    pub fn set_byte(&mut self, offset: u32, byte: u8) {
+        if offset == 0 {
+            self.speed = byte;
+            return;
+        }
+        let item = &mut self.items[((offset - 1) / 3) as usize % 32];
+        match (offset - 1) % 3 {
+            0 => item.wave_type = WaveType::from_u8(byte),
+            1 => item.sound = byte,
+            _ => item.volume = byte,
+        }
+    }
+
+    pub fn from_memory(memory: &MemorySection, mut offset: u32) -> Self {
         let mut new = Self::new();
         new.speed = memory.get_at_addr_d(offset);
         offset += 1;
@@ -127,22 +165,6 @@ impl Audio {
         new
     }
 
-    pub fn to_string(&self) -> String {
-        let mut vec: Vec<u8> = Vec::new();
-        vec.push(self.speed);
-        for audio_item in self.items.iter() {
-            vec.push(audio_item.sound);
-            vec.push(audio_item.volume);
-            vec.push(audio_item.wave_type as u8);
-        }
-
-        let mut str = String::with_capacity(vec.len() * 2);
-        for v in vec {
-            str.push_str(&to_hex(v));
-        }
-        str
-    }
-
     pub fn from_string(str: String) -> Self {
         let mut vec: Vec<u8> = Vec::with_capacity(str.len() / 2);
 
@@ -164,6 +186,18 @@ impl Audio {
     }
 }
 
+impl Display for Audio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&to_hex(self.speed))?;
+        for audio_item in self.items.iter() {
+            f.write_str(&to_hex(audio_item.sound))?;
+            f.write_str(&to_hex(audio_item.volume))?;
+            f.write_str(&to_hex(audio_item.wave_type as u8))?;
+        }
+        Ok(())
+    }
+}
+
 static mut PHASE: f32 = 0.0;
 
 pub fn get_amplitude(out: &mut [f32]) {
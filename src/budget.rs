@@ -0,0 +1,170 @@
+use crate::{c_singleton, gamestate::{gamedata_to_string, get_code}, get_s_val, set_s_val, Singleton};
+
+#[derive(Clone, Copy)]
+pub struct Limits {
+    pub tokens: usize,
+    pub chars: usize,
+    pub size: usize,
+    // lua instructions per frame before the watchdog stops the cart
+    pub instructions: usize,
+    // bytes the lua state of a cart may allocate
+    pub memory: usize,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Usage {
+    pub tokens: usize,
+    pub chars: usize,
+    pub size: usize,
+}
+
+c_singleton!(LIMITS, Limits, || Limits {
+    tokens: 8192,
+    chars: 65535,
+    size: 131072,
+    instructions: 5_000_000,
+    memory: 8 * 1024 * 1024,
+});
+c_singleton!(USAGE, Usage, Usage::default);
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// returns the index after the closing `]]` of a long string or comment starting at `i`
+fn skip_long_bracket(chars: &[char], mut i: usize) -> usize {
+    while i + 1 < chars.len() && !(chars[i] == ']' && chars[i + 1] == ']') {
+        i += 1;
+    }
+    i + 2
+}
+
+// counts the lua tokens in the code. comments, `,`, `;`, `.`, closing brackets and `end` are free
+pub fn count_tokens(code: &str) -> usize {
+    let chars: Vec<char> = code.chars().collect();
+    let mut tokens = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '-' && next == Some('-') {
+            if chars.get(i + 2) == Some(&'[') && chars.get(i + 3) == Some(&'[') {
+                i = skip_long_bracket(&chars, i + 4);
+            } else {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+        } else if c == '[' && next == Some('[') {
+            i = skip_long_bracket(&chars, i + 2);
+            tokens += 1;
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens += 1;
+        } else if is_ident_char(c) || (c == '.' && next.is_some_and(|c| c.is_ascii_digit())) {
+            let is_number = c.is_ascii_digit() || c == '.';
+            let mut word = String::new();
+            while i < chars.len() && (is_ident_char(chars[i]) || (is_number && chars[i] == '.')) {
+                word.push(chars[i]);
+                i += 1;
+            }
+            if word != "end" {
+                tokens += 1;
+            }
+        } else {
+            // operators made up of multiple characters count as one token
+            let two: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let three: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            if three == "..." {
+                i += 3;
+            } else if ["==", "~=", "<=", ">=", "..", "//", "<<", ">>", "::"].contains(&two.as_str()) {
+                i += 2;
+            } else {
+                i += 1;
+            }
+            if !matches!(c, ',' | ';' | '.' | ')' | ']' | '}') || three == "..." || two == ".." {
+                tokens += 1;
+            }
+        }
+    }
+
+    tokens
+}
+
+// recounts the usage of the loaded cart
+pub fn refresh() -> Usage {
+    let code = get_code().join("\n");
+    let usage = Usage {
+        tokens: count_tokens(&code),
+        chars: code.chars().count(),
+        size: gamedata_to_string().len(),
+    };
+    set_s_val!(USAGE, usage);
+    usage
+}
+
+pub fn get_usage() -> Usage {
+    *get_s_val!(USAGE)
+}
+
+pub fn get_limits() -> Limits {
+    *get_s_val!(LIMITS)
+}
+
+pub fn set_limits(limits: Limits) {
+    set_s_val!(LIMITS, limits);
+}
+
+// a message for every limit the cart exceeds
+pub fn exceeded_limits(usage: &Usage) -> Vec<String> {
+    let limits = get_limits();
+    let mut vec: Vec<String> = Vec::new();
+    if usage.tokens > limits.tokens {
+        vec.push(format!("too many tokens: {}/{}", usage.tokens, limits.tokens));
+    }
+    if usage.chars > limits.chars {
+        vec.push(format!("too many characters: {}/{}", usage.chars, limits.chars));
+    }
+    if usage.size > limits.size {
+        vec.push(format!("cart too big: {}/{} bytes", usage.size, limits.size));
+    }
+    vec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::count_tokens;
+
+    #[test]
+    fn counts_names_numbers_and_operators() {
+        assert_eq!(count_tokens("local x = 1 + 2"), 6);
+        assert_eq!(count_tokens("a = b .. c"), 5);
+        assert_eq!(count_tokens("x = .5 + 0x1f"), 5);
+        assert_eq!(count_tokens("if a >= b then end"), 5);
+    }
+
+    #[test]
+    fn some_tokens_are_free() {
+        assert_eq!(count_tokens("function f() end"), 3);
+        assert_eq!(count_tokens("t = {1, 2};"), 5);
+        assert_eq!(count_tokens("t.x"), 2);
+    }
+
+    #[test]
+    fn comments_are_free_and_strings_are_one_token() {
+        assert_eq!(count_tokens("-- a comment\nprint(\"hi -- there\") -- more"), 3);
+        assert_eq!(count_tokens("--[[ long\ncomment ]] s = [[long\nstring]]"), 3);
+        assert_eq!(count_tokens("s = 'it\\'s'"), 3);
+        assert_eq!(count_tokens(""), 0);
+    }
+}
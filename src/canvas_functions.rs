@@ -3,19 +3,38 @@
 use std::sync::atomic::{AtomicI32, Ordering::Relaxed};
 
 use crate::charmap::put_char_on_canvas;
-use crate::gamestate::get_image_vec;
-use crate::memory::displaymemory;
+use crate::gamestate::{get_font, get_palettes};
+use crate::memory::{
+    colormemory, displaymemory, drawstatememory, spritememory, MemorySection, CLIP_ADDR, DISPLAY_OFFSET,
+    DISPLAY_PALETTE_ADDR, FILLP_ADDR, FILLP_COLOR_ADDR, SCREEN_MODE_ADDR,
+};
 use crate::singleton::Singleton;
 use crate::{c_singleton, get_s_val, get_s_val_c, set_s_val, swap, HEIGHT, WIDTH};
 
-c_singleton!(MIN_X, i32, || 0);
-c_singleton!(MIN_Y, i32, || 0);
-c_singleton!(MAX_X, i32, || WIDTH as i32);
-c_singleton!(MAX_Y, i32, || HEIGHT as i32);
-
 c_singleton!(OX, i32, || 0);
 c_singleton!(OY, i32, || 0);
 
+// everything pushstate saves: camera, cursor and the draw registers
+struct DrawState {
+    ox: i32,
+    oy: i32,
+    cursor_x: i32,
+    cursor_y: i32,
+    registers: Vec<u8>,
+}
+
+static MAX_STATES: usize = 64;
+// the registers saved by pushstate: pal, palt and the clip rectangle
+fn state_registers() -> impl Iterator<Item = (&'static MemorySection, u32)> {
+    let displaymem: &MemorySection = get_s_val!(displaymemory);
+    let drawstatemem: &MemorySection = get_s_val!(drawstatememory);
+    (0..0x10)
+        .chain(0x11..0x13)
+        .map(move |i| (displaymem, i))
+        .chain((CLIP_ADDR..CLIP_ADDR + 4).map(move |i| (drawstatemem, i)))
+}
+c_singleton!(STATE_STACK, Vec<DrawState>, Vec::new);
+
 #[derive(Clone, Copy)]
 pub struct Color(u8, u8, u8);
 
@@ -42,8 +61,15 @@ impl Color {
     pub fn get_values(&self) -> (u8, u8, u8) {
         (self.0, self.1, self.2)
     }
+
+    pub fn to_hex(&self) -> u32 {
+        (self.0 as u32) << 16 | (self.1 as u32) << 8 | self.2 as u32
+    }
 }
 
+// the builtin palettes, carts can replace them with their own
+pub static PALETTES: [&[Color; 16]; 4] = [&PALETTE1, &PALETTE2, &PALETTE3, &PALETTE4];
+
 pub static PALETTE1: [Color; 16] = [
     Color::from_hex(0x1a1c2c),
     Color::from_hex(0x5d275d),
@@ -117,32 +143,116 @@ static PALETTE4: [Color; 16] = [
     Color::from_hex(0x5f574f),
 ];
 
-static SCREEN_MEM_LEN: u32 = WIDTH * HEIGHT;
+// width, height and pixel size of every screen mode. smaller screens are centered in the window
+pub static SCREEN_MODES: [(u32, u32, u32); 3] = [(200, 180, 1), (100, 90, 2), (128, 128, 1)];
+
+#[inline(always)]
+pub fn screen_mode() -> (u32, u32, u32) {
+    let mode = get_s_val!(drawstatememory).get_at_addr_d(SCREEN_MODE_ADDR);
+    *SCREEN_MODES.get(mode as usize).unwrap_or(&SCREEN_MODES[0])
+}
+
+#[inline(always)]
+pub fn screen_width() -> u32 {
+    screen_mode().0
+}
+
+#[inline(always)]
+pub fn screen_height() -> u32 {
+    screen_mode().1
+}
+
+// switches the screen mode and resets the clip rectangle to the new screen
+pub fn set_screen_mode(mode: u8) {
+    if (mode as usize) < SCREEN_MODES.len() {
+        get_s_val!(drawstatememory).set_at_addr(SCREEN_MODE_ADDR, mode);
+        clip(None, None, None, None);
+    }
+}
+
+// the offset of the screen in the window and its pixel size
+fn screen_placement() -> (u32, u32, u32) {
+    let (w, h, size) = screen_mode();
+    ((WIDTH - w * size) / 2, (HEIGHT - h * size) / 2, size)
+}
+
+// the pixel of the screen that is shown at x, y of the window
+pub fn window_to_screen(x: u32, y: u32) -> Option<(u32, u32)> {
+    let (ox, oy, size) = screen_placement();
+    if x < ox || y < oy {
+        return None;
+    }
+    let (x, y) = ((x - ox) / size, (y - oy) / size);
+    if x >= screen_width() || y >= screen_height() {
+        None
+    } else {
+        Some((x, y))
+    }
+}
 
+// like window_to_screen, but positions outside of the screen stick to its nearest edge
+pub fn window_to_screen_clamped(x: u32, y: u32) -> (u32, u32) {
+    let (ox, oy, size) = screen_placement();
+    (
+        (x.saturating_sub(ox) / size).min(screen_width() - 1),
+        (y.saturating_sub(oy) / size).min(screen_height() - 1),
+    )
+}
+
+// the color index shown at x, y of the window, None for the border around smaller screens
+pub fn window_color_index(x: u32, y: u32) -> Option<u8> {
+    window_to_screen(x, y).map(|(x, y)| {
+        get_s_val!(displaymemory).get_at_addr_d(y * screen_width() + x + DISPLAY_OFFSET)
+    })
+}
+
+// the color of a pixel on the screen. the draw palette (pal) was applied when it was drawn, the display palette is applied here
 #[inline(always)]
 pub fn color_index_to_color(mut index: u8) -> Color {
     index %= 16;
-    index = get_s_val!(displaymemory).get_at_addr_d(index as u32) % 16;
+    index = get_s_val!(drawstatememory).get_at_addr_d(DISPLAY_PALETTE_ADDR + index as u32) % 16;
+    let colormem = get_s_val!(colormemory);
+    let off = index as u32 * 4;
+    if colormem.get_at_addr_d(off + 3) > 0 {
+        return Color::rgb(
+            colormem.get_at_addr_d(off),
+            colormem.get_at_addr_d(off + 1),
+            colormem.get_at_addr_d(off + 2),
+        );
+    }
     let paletteindex = get_s_val!(displaymemory).get_at_addr_d(16) % 4;
-    let palette = {
-        if paletteindex == 0 {
-            PALETTE1
-        } else if paletteindex == 1 {
-            PALETTE2
-        } else if paletteindex == 2 {
-            PALETTE3
-        } else {
-            PALETTE4
-        }
-    };
-    palette[index as usize]
+    get_palette(paletteindex)[index as usize]
+}
+
+// the colors of a palette, the cart's own if it has them
+#[inline(always)]
+pub fn get_palette(index: u8) -> [Color; 16] {
+    match get_palettes() {
+        Some(palettes) => palettes[index as usize % 4],
+        None => *PALETTES[index as usize % 4],
+    }
+}
+
+// sets the color of a color index until the cart stops. without r, g and b the palette's color is used again
+pub fn setrgb(index: u8, rgb: Option<(u8, u8, u8)>) {
+    let colormem = get_s_val!(colormemory);
+    let off = (index % 16) as u32 * 4;
+    let (r, g, b) = rgb.unwrap_or((0, 0, 0));
+    colormem.set_at_addr(off, r);
+    colormem.set_at_addr(off + 1, g);
+    colormem.set_at_addr(off + 2, b);
+    colormem.set_at_addr(off + 3, rgb.is_some() as u8);
 }
 
 pub fn sdl_apply_canvas(memory: &mut Vec<u8>) {
     memory.clear();
-    let screenmem = get_s_val!(displaymemory);
-    for i in 0..SCREEN_MEM_LEN {
-        color_index_to_color(screenmem.get_at_addr_d(i + 19)).sdl_write_to_vec(memory);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            match window_color_index(x, y) {
+                Some(index) => color_index_to_color(index).sdl_write_to_vec(memory),
+                None => Color::rgb(0, 0, 0).sdl_write_to_vec(memory),
+            }
+        }
     }
 }
 
@@ -152,8 +262,70 @@ pub fn set_pixel(x: i32, y: i32, color: u8) {
         return;
     }
 
+    if let Some(color) = pattern_color(x, y, color) {
+        put_pixel(x, y, color);
+    }
+}
+
+// the color index of the pixel on the screen, 0 if it's off the screen
+pub fn get_pixel(x: i32, y: i32) -> u8 {
+    if x < 0 || y < 0 || x >= screen_width() as i32 || y >= screen_height() as i32 {
+        return 0;
+    }
+    get_s_val!(displaymemory).get_at_addr_d((y * screen_width() as i32 + x) as u32 + DISPLAY_OFFSET)
+}
+
+// the color index of the pixel in the sprite memory, 0 if it's off the sheet
+pub fn sget(x: i32, y: i32) -> u8 {
+    if x < 0 || y < 0 || x >= 128 || y >= 128 {
+        return 0;
+    }
+    get_s_val!(spritememory).get_at_addr_d((y * 128 + x) as u32)
+}
+
+pub fn sset(x: i32, y: i32, color: u8) {
+    if x < 0 || y < 0 || x >= 128 || y >= 128 {
+        return;
+    }
+    get_s_val!(spritememory).set_at_addr((y * 128 + x) as u32, color % 16);
+}
+
+// like set_pixel, but ignores the fill pattern. used for sprites and text
+#[inline(always)]
+pub fn put_pixel(x: i32, y: i32, color: u8) {
+    if !in_bounds(x, y) {
+        return;
+    }
+
     if let Some(c) = get_color(color) {
-        get_s_val!(displaymemory).set_at_addr((y * WIDTH as i32 + x + 0x13).abs() as u32, c);
+        get_s_val!(displaymemory).set_at_addr((y * screen_width() as i32 + x) as u32 + DISPLAY_OFFSET, c);
+    }
+}
+
+pub fn fillp(pattern: Option<u16>, color: Option<u8>) {
+    let drawstatemem = get_s_val!(drawstatememory);
+    let pattern = pattern.unwrap_or(0);
+    drawstatemem.set_at_addr(FILLP_ADDR, (pattern & 0xff) as u8);
+    drawstatemem.set_at_addr(FILLP_ADDR + 1, (pattern >> 8) as u8);
+    drawstatemem.set_at_addr(FILLP_COLOR_ADDR, color.map_or(0, |c| (c % 16) | 0x10));
+}
+
+// the color of a pixel after applying the fill pattern. None if the pattern leaves it transparent
+#[inline(always)]
+fn pattern_color(x: i32, y: i32, color: u8) -> Option<u8> {
+    let drawstatemem = get_s_val!(drawstatememory);
+    let pattern = drawstatemem.get_at_addr_d(FILLP_ADDR) as u16
+        | (drawstatemem.get_at_addr_d(FILLP_ADDR + 1) as u16) << 8;
+    let bit = 15 - ((y & 3) * 4 + (x & 3));
+    if pattern >> bit & 1 == 0 {
+        Some(color)
+    } else {
+        let secondary = drawstatemem.get_at_addr_d(FILLP_COLOR_ADDR);
+        if secondary & 0x10 != 0 {
+            Some(secondary & 0xf)
+        } else {
+            None
+        }
     }
 }
 
@@ -192,11 +364,11 @@ fn to_safe_rect(
         y1 = 0;
     }
 
-    if x2 > WIDTH as i32 {
-        x2 = WIDTH as i32;
+    if x2 > screen_width() as i32 {
+        x2 = screen_width() as i32;
     }
-    if y2 > HEIGHT as i32 {
-        y2 = HEIGHT as i32;
+    if y2 > screen_height() as i32 {
+        y2 = screen_height() as i32;
     }
 
     if x2 == x1 || y2 == y1 {
@@ -208,9 +380,15 @@ fn to_safe_rect(
 
 pub fn clear(color: Option<u8>) {
     let mem = get_s_val!(displaymemory);
-    let c = mem.get_at_addr_d(color.unwrap_or(0) as u32);
-    for i in 0..SCREEN_MEM_LEN {
-        get_s_val!(displaymemory).set_at_addr(i + 19, c);
+    let color = color.unwrap_or(0) % 16;
+    let width = screen_width();
+    for i in 0..width * screen_height() {
+        // cls ignores palt, but not pal
+        let x = (i % width) as i32;
+        let y = (i / width) as i32;
+        if let Some(c) = pattern_color(x, y, color) {
+            mem.set_at_addr(i + DISPLAY_OFFSET, mem.get_at_addr_d(c as u32));
+        }
     }
 }
 
@@ -266,15 +444,176 @@ pub fn circle(cx: i32, cy: i32, r: i32, color: u8) {
     }
 }
 
+// a horizontal line in screen coordinates, the camera has to be applied already
+fn hline(x1: i32, x2: i32, y: i32, color: u8) {
+    if y < 0 || y >= screen_height() as i32 {
+        return;
+    }
+    for x in x1.max(0)..=x2.min(screen_width() as i32 - 1) {
+        set_pixel(x, y, color);
+    }
+}
+
+pub fn circfill(cx: i32, cy: i32, r: i32, color: u8) {
+    if r < 0 {
+        return;
+    }
+    let cx = (cx + get_s_val_c!(OX)) as i64;
+    let cy = (cy + get_s_val_c!(OY)) as i64;
+    let (width, height) = (screen_width() as i64, screen_height() as i64);
+    // no pixel of the screen is further away than this, a bigger circle covers all of it anyway.
+    // i64 because r * r overflows i32 for big circles
+    let r = (r as i64).min(cx.abs() + cy.abs() + width + height);
+    // only the rows on the screen
+    for dy in (-r).max(-cy)..=r.min(height - 1 - cy) {
+        // + r rounds the edges like a midpoint circle would
+        let dx = ((r * r - dy * dy + r) as f64).sqrt() as i64;
+        hline(
+            (cx - dx).max(-1) as i32,
+            (cx + dx).min(width) as i32,
+            (cy + dy) as i32,
+            color,
+        );
+    }
+}
+
+pub fn ovalfill(cx: i32, cy: i32, rx: i32, ry: i32, color: u8) {
+    if rx < 0 || ry < 0 {
+        return;
+    }
+    let cx = cx + get_s_val_c!(OX);
+    let cy = cy + get_s_val_c!(OY);
+    for dy in -ry..=ry {
+        let ny = dy as f32 / (ry as f32 + 0.5);
+        let dx = ((rx as f32 + 0.5) * (1.0 - ny * ny).sqrt()) as i32;
+        hline(cx - dx, cx + dx, cy + dy, color);
+    }
+}
+
+// scanline fill with the even-odd rule. the pixel at (x, y) is filled if its top left corner is inside
+pub fn polyfill(points: &[(i32, i32)], color: u8) {
+    if points.len() < 3 {
+        return;
+    }
+    let ox = get_s_val_c!(OX);
+    let oy = get_s_val_c!(OY);
+    let points: Vec<(f32, f32)> = points
+        .iter()
+        .map(|(x, y)| ((x + ox) as f32, (y + oy) as f32))
+        .collect();
+
+    let min_y = points.iter().map(|p| p.1 as i32).min().unwrap_or(0).max(0);
+    let max_y = points
+        .iter()
+        .map(|p| p.1 as i32)
+        .max()
+        .unwrap_or(0)
+        .min(screen_height() as i32 - 1);
+
+    let mut xs: Vec<f32> = Vec::new();
+    for y in min_y..=max_y {
+        let fy = y as f32;
+        xs.clear();
+        for i in 0..points.len() {
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[(i + 1) % points.len()];
+            if (y1 <= fy && fy < y2) || (y2 <= fy && fy < y1) {
+                xs.push(x1 + (fy - y1) / (y2 - y1) * (x2 - x1));
+            }
+        }
+        xs.sort_by(|a, b| a.total_cmp(b));
+        for pair in xs.chunks(2) {
+            if pair.len() == 2 {
+                hline(pair[0].ceil() as i32, pair[1].ceil() as i32 - 1, y, color);
+            }
+        }
+    }
+}
+
+pub fn trifill(x1: i32, y1: i32, x2: i32, y2: i32, x3: i32, y3: i32, color: u8) {
+    polyfill(&[(x1, y1), (x2, y2), (x3, y3)], color);
+}
+
+pub fn poly(points: &[(i32, i32)], color: u8, fill: bool) {
+    if fill {
+        polyfill(points, color);
+    } else {
+        for i in 0..points.len() {
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[(i + 1) % points.len()];
+            line(x1, y1, x2, y2, color);
+        }
+    }
+}
+
+// whether the pixel is on the screen and inside the clip rectangle
 pub fn in_bounds(x: i32, y: i32) -> bool {
+    let drawstatemem = get_s_val!(drawstatememory);
     !(x < 0
-        // || x < get_s_val_c!(MIN_X)
-        || x >= WIDTH as i32
-        // || x >= get_s_val_c!(MAX_X)
+        || x < drawstatemem.get_at_addr_d(CLIP_ADDR) as i32
+        || x >= screen_width() as i32
+        || x >= drawstatemem.get_at_addr_d(CLIP_ADDR + 2) as i32
         || y < 0
-        // || y < get_s_val_c!(MIN_Y)
-        || y >= HEIGHT as i32)
-    // || y >= get_s_val_c!(MAX_Y))
+        || y < drawstatemem.get_at_addr_d(CLIP_ADDR + 1) as i32
+        || y >= screen_height() as i32
+        || y >= drawstatemem.get_at_addr_d(CLIP_ADDR + 3) as i32)
+}
+
+// the part of the clip rectangle on the screen: x1, y1, x2, y2 with x2 and y2 exclusive
+fn clip_rect() -> (i32, i32, i32, i32) {
+    let drawstatemem = get_s_val!(drawstatememory);
+    let (width, height) = (screen_width() as i32, screen_height() as i32);
+    (
+        (drawstatemem.get_at_addr_d(CLIP_ADDR) as i32).min(width),
+        (drawstatemem.get_at_addr_d(CLIP_ADDR + 1) as i32).min(height),
+        (drawstatemem.get_at_addr_d(CLIP_ADDR + 2) as i32).min(width),
+        (drawstatemem.get_at_addr_d(CLIP_ADDR + 3) as i32).min(height),
+    )
+}
+
+// the clip rectangle is in screen coordinates, the camera doesn't move it
+pub fn clip(x: Option<i32>, y: Option<i32>, w: Option<i32>, h: Option<i32>) {
+    let (width, height) = (screen_width() as i32, screen_height() as i32);
+    let (x, y) = (x.unwrap_or(0) as i64, y.unwrap_or(0) as i64);
+    let x2 = w.map_or(width as i64, |w| x + w.max(0) as i64).clamp(0, width as i64);
+    let y2 = h.map_or(height as i64, |h| y + h.max(0) as i64).clamp(0, height as i64);
+    let x1 = x.clamp(0, x2);
+    let y1 = y.clamp(0, y2);
+    let drawstatemem = get_s_val!(drawstatememory);
+    drawstatemem.set_at_addr(CLIP_ADDR, x1 as u8);
+    drawstatemem.set_at_addr(CLIP_ADDR + 1, y1 as u8);
+    drawstatemem.set_at_addr(CLIP_ADDR + 2, x2 as u8);
+    drawstatemem.set_at_addr(CLIP_ADDR + 3, y2 as u8);
+}
+
+pub fn pushstate() {
+    let stack = get_s_val!(STATE_STACK);
+    if stack.len() >= MAX_STATES {
+        return;
+    }
+    stack.push(DrawState {
+        ox: get_s_val_c!(OX),
+        oy: get_s_val_c!(OY),
+        cursor_x: CURSORX.load(Relaxed),
+        cursor_y: CURSORY.load(Relaxed),
+        registers: state_registers().map(|(mem, i)| mem.get_at_addr_d(i)).collect(),
+    });
+}
+
+pub fn popstate() {
+    if let Some(state) = get_s_val!(STATE_STACK).pop() {
+        set_s_val!(OX, state.ox);
+        set_s_val!(OY, state.oy);
+        CURSORX.store(state.cursor_x, Relaxed);
+        CURSORY.store(state.cursor_y, Relaxed);
+        for ((mem, i), byte) in state_registers().zip(state.registers) {
+            mem.set_at_addr(i, byte);
+        }
+    }
+}
+
+pub fn clear_states() {
+    get_s_val!(STATE_STACK).clear();
 }
 
 pub fn line(mut x1: i32, mut y1: i32, mut x2: i32, mut y2: i32, color: u8) {
@@ -289,23 +628,23 @@ pub fn line(mut x1: i32, mut y1: i32, mut x2: i32, mut y2: i32, color: u8) {
     let dy: i32 = if y1 < y2 { y2 - y1 } else { y1 - y2 };
 
     let mut err: i32 = dx - dy;
-    #[allow(unused)]
-    let mut e2: i32 = err * 2;
 
-    while x1 != x2 && y1 != y2 {
-        e2 = err * 2;
+    loop {
+        if in_bounds(x1, y1) {
+            set_pixel(x1, y1, color);
+        }
+        if x1 == x2 && y1 == y2 {
+            break;
+        }
+        let e2 = err * 2;
         if e2 > -dy {
-            err = err - dy;
-            x1 = x1 + sx;
+            err -= dy;
+            x1 += sx;
         }
         if e2 < dx {
-            err = err + dx;
-            y1 = y1 + sy;
+            err += dx;
+            y1 += sy;
         }
-        if !in_bounds(x1, y1) {
-            continue;
-        }
-        set_pixel(x1, y1, color)
     }
 }
 
@@ -322,14 +661,21 @@ pub fn camera(x: Option<i32>, y: Option<i32>) {
     }
 }
 
-pub fn pal(col1: Option<u8>, col2: Option<u8>) {
+// p 0 changes the draw palette, p 1 the display palette. without a color both get reset
+pub fn pal(col1: Option<u8>, col2: Option<u8>, p: Option<u8>) {
     let displaymem = get_s_val!(displaymemory);
+    let drawstatemem = get_s_val!(drawstatememory);
     if let Some(c1) = col1 {
         let c2 = col2.unwrap_or(c1);
-        displaymem.set_at_addr((c1 % 16) as u32, c2 % 16);
+        if p == Some(1) {
+            drawstatemem.set_at_addr(DISPLAY_PALETTE_ADDR + (c1 % 16) as u32, c2 % 16);
+        } else {
+            displaymem.set_at_addr((c1 % 16) as u32, c2 % 16);
+        }
     } else {
         for i in 0..16 {
             displaymem.set_at_addr(i, i as u8);
+            drawstatemem.set_at_addr(DISPLAY_PALETTE_ADDR + i, i as u8);
         }
     }
 }
@@ -337,16 +683,17 @@ pub fn pal(col1: Option<u8>, col2: Option<u8>) {
 pub fn palt(col1: Option<u8>, transparency: Option<bool>) {
     let displaymem = get_s_val!(displaymemory);
     if let Some(c1) = col1 {
-        let t = transparency.unwrap_or(if c1 == 0 { true } else { false });
+        let t = transparency.unwrap_or(c1 == 0);
         let mut byte = displaymem.get_at_addr_d(17 + c1 as u32 / 8);
         byte &= 0xff ^ (1 << (c1 % 8));
         if t {
-            byte |= 1 << c1 % 8;
+            byte |= 1 << (c1 % 8);
         }
         displaymem.set_at_addr(17 + c1 as u32 / 8, byte);
     } else {
-        displaymem.set_at_addr(16, 1);
-        displaymem.set_at_addr(17, 0);
+        // only color 0 is transparent
+        displaymem.set_at_addr(17, 1);
+        displaymem.set_at_addr(18, 0);
     }
 }
 
@@ -358,7 +705,7 @@ pub fn switch_palette(palette: u8) {
 pub fn get_color(mut color: u8) -> Option<u8> {
     color %= 16;
     let displaymem = get_s_val!(displaymemory);
-    if (displaymem.get_at_addr_d(17 + color as u32 / 8) >> color % 8) & 1 > 0 {
+    if (displaymem.get_at_addr_d(17 + color as u32 / 8) >> (color % 8)) & 1 > 0 {
         None
     } else {
         Some(displaymem.get_at_addr_d(color as u32))
@@ -396,51 +743,231 @@ pub fn print<T: Into<String>>(text: T, x: Option<i32>, y: Option<i32>, color: Op
         _ => 12,
     });
 
+    // the cart's font is used for every glyph it defines
+    let font = get_font();
+    let line_height = font.as_ref().map_or(6, |font| font.line_height as i32);
+
     while i < bytes.len() {
         match bytes[i] {
-            '\n' => cursor(Some(x.unwrap_or(0)), Some(CURSORY.load(Relaxed) + 6)),
-            _ => unsafe {
-                CURSORX.fetch_add(
-                    put_char_on_canvas(
-                        bytes[i],
-                        x.unwrap_or(CURSORX.load(Relaxed)),
-                        y.unwrap_or(CURSORY.load(Relaxed)),
-                        col,
-                    ) as i32,
-                    Relaxed,
-                );
-            },
+            '\n' => cursor(Some(x.unwrap_or(0)), Some(CURSORY.load(Relaxed) + line_height)),
+            _ => {
+                let (cx, cy) = (CURSORX.load(Relaxed), CURSORY.load(Relaxed));
+                let width = font
+                    .as_ref()
+                    .and_then(|font| font.put_char(bytes[i], cx, cy, col, put_pixel))
+                    .unwrap_or_else(|| put_char_on_canvas(bytes[i], cx, cy, col));
+                CURSORX.fetch_add(width as i32, Relaxed);
+            }
         };
         i += 1;
     }
-    cursor(Some(x.unwrap_or(0)), Some(CURSORY.load(Relaxed) + 6));
+    cursor(Some(x.unwrap_or(0)), Some(CURSORY.load(Relaxed) + line_height));
 }
 
-pub fn sspr(sx: i32, sy: i32, x: u32, y: u32, w: u32, h: u32) {
-    if x >= 128 || y >= 128 {
+// draws the w*h pixels at x, y of the sprite sheet stretched to dw*dh pixels at sx, sy
+#[allow(clippy::too_many_arguments)]
+pub fn sspr(
+    sx: i32,
+    sy: i32,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    dw: Option<u32>,
+    dh: Option<u32>,
+    flip_x: bool,
+    flip_y: bool,
+) {
+    if x >= 128 || y >= 128 || w == 0 || h == 0 {
         return;
     }
+    let dw = dw.unwrap_or(w) as i64;
+    let dh = dh.unwrap_or(h) as i64;
+    let sx = sx as i64 + get_s_val_c!(OX) as i64;
+    let sy = sy as i64 + get_s_val_c!(OY) as i64;
 
-    let vec = get_image_vec();
+    let sprites = get_s_val!(spritememory);
 
-    for oy in 0..h {
-        if y + oy >= 128 {
-            break;
+    // only the destination pixels inside the clip rectangle
+    let (x1, y1, x2, y2) = clip_rect();
+    for oy in (y1 as i64 - sy).max(0)..(y2 as i64 - sy).min(dh) {
+        // nearest neighbor
+        let mut py = (oy * h as i64 / dh) as u32;
+        if flip_y {
+            py = h - 1 - py;
+        }
+        if y + py >= 128 {
+            continue;
         }
-        for ox in 0..w {
-            if x + ox >= 128 {
-                break;
+        for ox in (x1 as i64 - sx).max(0)..(x2 as i64 - sx).min(dw) {
+            let mut px = (ox * w as i64 / dw) as u32;
+            if flip_x {
+                px = w - 1 - px;
+            }
+            if x + px >= 128 {
+                continue;
             }
 
-            let off = (y + oy) * 128 + x + ox;
-            set_pixel(sx + ox as i32, sy + oy as i32, vec[off as usize]);
+            let off = (y + py) * 128 + x + px;
+            // put_pixel leaves out the colors made transparent with palt
+            put_pixel((sx + ox) as i32, (sy + oy) as i32, sprites.get_at_addr_d(off));
         }
     }
 }
 
-pub fn spr(idx: u32, x: i32, y: i32) {
+// draws the sw*sh pixels at sx, sy of the sprite sheet rotated around their center and scaled.
+// x, y is the top left corner of the unrotated sprite, the angle is in turns and counterclockwise like tsin
+#[allow(clippy::too_many_arguments)]
+pub fn rspr(sx: u32, sy: u32, sw: u32, sh: u32, x: i32, y: i32, angle: f64, scale: f64) {
+    if sx >= 128 || sy >= 128 || sw == 0 || sh == 0 || scale <= 0.0 {
+        return;
+    }
+    let sw = sw.min(128 - sx) as f64;
+    let sh = sh.min(128 - sy) as f64;
+    let (sin, cos) = (angle * std::f64::consts::TAU).sin_cos();
+
+    // center of the sprite on the screen
+    let cx = x as f64 + get_s_val_c!(OX) as f64 + sw * scale / 2.0;
+    let cy = y as f64 + get_s_val_c!(OY) as f64 + sh * scale / 2.0;
+    let radius = (sw * sw + sh * sh).sqrt() * scale / 2.0;
+
+    let sprites = get_s_val!(spritememory);
+
+    // the bounding box of the rotated sprite, limited to the clip rectangle
+    let (x1, y1, x2, y2) = clip_rect();
+    let (px1, px2) = (((cx - radius).floor() as i32).max(x1), ((cx + radius).ceil() as i32).min(x2 - 1));
+    let (py1, py2) = (((cy - radius).floor() as i32).max(y1), ((cy + radius).ceil() as i32).min(y2 - 1));
+    for py in py1..=py2 {
+        for px in px1..=px2 {
+            // inverse mapping: rotate the center of the screen pixel back into the sprite
+            let dx = px as f64 + 0.5 - cx;
+            let dy = py as f64 + 0.5 - cy;
+            let u = (dx * cos - dy * sin) / scale + sw / 2.0;
+            let v = (dx * sin + dy * cos) / scale + sh / 2.0;
+            if u < 0.0 || v < 0.0 || u >= sw || v >= sh {
+                continue;
+            }
+
+            let off = (sy + v as u32) * 128 + sx + u as u32;
+            put_pixel(px, py, sprites.get_at_addr_d(off));
+        }
+    }
+}
+
+// w and h are in tiles and may be fractions of a tile
+pub fn spr(idx: u32, x: i32, y: i32, w: Option<f64>, h: Option<f64>, flip_x: bool, flip_y: bool) {
     if idx >= 255 {
         return;
     }
-    sspr(x, y, idx % 16 * 8, idx / 16 * 8, 8, 8);
+    let w = (w.unwrap_or(1.0).max(0.0) * 8.0) as u32;
+    let h = (h.unwrap_or(1.0).max(0.0) * 8.0) as u32;
+    sspr(x, y, idx % 16 * 8, idx / 16 * 8, w, h, None, None, flip_x, flip_y);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, MutexGuard};
+
+    use super::*;
+    use crate::memory::init_test_memory;
+
+    // the screen and the draw state are global, so the tests that use them take turns
+    static SCREEN: Mutex<()> = Mutex::new(());
+
+    // mode 0, no camera, clip, fill pattern or palette changes and a black screen
+    fn reset_screen() -> MutexGuard<'static, ()> {
+        let guard = SCREEN.lock().unwrap_or_else(|e| e.into_inner());
+        init_test_memory();
+        set_screen_mode(0);
+        camera(None, None);
+        fillp(None, None);
+        pal(None, None, None);
+        clear(Some(0));
+        guard
+    }
+
+    #[test]
+    fn window_to_screen_follows_the_screen_mode() {
+        let _screen = reset_screen();
+
+        set_screen_mode(0);
+        assert_eq!(window_to_screen(0, 0), Some((0, 0)));
+        assert_eq!(window_to_screen(199, 179), Some((199, 179)));
+        assert_eq!(window_to_screen(200, 0), None);
+
+        // 100x90 with two window pixels per screen pixel
+        set_screen_mode(1);
+        assert_eq!(window_to_screen(0, 0), Some((0, 0)));
+        assert_eq!(window_to_screen(21, 11), Some((10, 5)));
+        assert_eq!(window_to_screen(199, 179), Some((99, 89)));
+
+        // 128x128 in the middle of the window
+        set_screen_mode(2);
+        assert_eq!(window_to_screen(36, 26), Some((0, 0)));
+        assert_eq!(window_to_screen(163, 153), Some((127, 127)));
+        assert_eq!(window_to_screen(35, 26), None);
+        assert_eq!(window_to_screen(36, 154), None);
+        assert_eq!(window_to_screen_clamped(0, 179), (0, 127));
+
+        set_screen_mode(0);
+    }
+
+    #[test]
+    fn huge_circles_cover_the_screen() {
+        let _screen = reset_screen();
+        circfill(100, 90, 50_000, 7);
+        assert_eq!(get_pixel(0, 0), 7);
+        assert_eq!(get_pixel(199, 179), 7);
+
+        clear(Some(0));
+        circfill(i32::MAX / 2, 90, i32::MAX, 8);
+        assert_eq!(get_pixel(0, 0), 8);
+        assert_eq!(get_pixel(199, 179), 8);
+    }
+
+    #[test]
+    fn clip_keeps_the_size_of_rectangles_off_the_screen() {
+        let _screen = reset_screen();
+        clip(Some(-10), Some(0), Some(20), Some(20));
+        assert!(in_bounds(9, 0));
+        assert!(!in_bounds(10, 0));
+        assert!(!in_bounds(0, 20));
+
+        clip(Some(190), Some(170), Some(20), Some(20));
+        assert!(in_bounds(199, 179));
+        assert!(!in_bounds(189, 179));
+
+        clip(Some(300), Some(0), None, None);
+        assert!(!in_bounds(199, 0));
+        clip(None, None, None, None);
+    }
+
+    #[test]
+    fn huge_sprites_only_draw_the_clip_rectangle() {
+        let _screen = reset_screen();
+        sset(0, 0, 9);
+        sspr(0, 0, 0, 0, 1, 1, Some(u32::MAX), Some(u32::MAX), false, false);
+        assert_eq!(get_pixel(0, 0), 9);
+        assert_eq!(get_pixel(199, 179), 9);
+
+        clear(Some(0));
+        clip(Some(10), Some(10), Some(5), Some(5));
+        sspr(-1000, -1000, 0, 0, 1, 1, Some(u32::MAX), Some(u32::MAX), false, false);
+        assert_eq!(get_pixel(10, 10), 9);
+        assert_eq!(get_pixel(14, 14), 9);
+        assert_eq!(get_pixel(15, 15), 0);
+        clip(None, None, None, None);
+        sset(0, 0, 0);
+    }
+
+    #[test]
+    fn huge_rotated_sprites_only_draw_the_clip_rectangle() {
+        let _screen = reset_screen();
+        sset(0, 0, 9);
+        rspr(0, 0, 1, 1, -499_900, -499_910, 0.125, 1e6);
+        assert_eq!(get_pixel(0, 0), 9);
+        assert_eq!(get_pixel(199, 179), 9);
+        rspr(0, 0, 1, 1, i32::MAX, i32::MIN, 0.0, f64::MAX);
+        sset(0, 0, 0);
+    }
 }
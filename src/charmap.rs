@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{c_singleton, get_s_val, set_pixel, Singleton};
+use crate::{c_singleton, get_s_val, put_pixel, Singleton};
 
 c_singleton!(CHARMAP, HashMap<char, u32>, || {
     let mut map = HashMap::new();
@@ -15,32 +15,58 @@ c_singleton!(CHARMAP, HashMap<char, u32>, || {
     map.insert('7', 0b111001001001001);
     map.insert('8', 0b111101111101111);
     map.insert('9', 0b111101111001001);
-    map.insert('a', 0b10101111101101);
-    map.insert('b', 0b110101110101110);
-    map.insert('c', 0b011100100100011);
-    map.insert('d', 0b110101101101110);
-    map.insert('e', 0b111100111100111);
-    map.insert('f', 0b111100110100100);
-    map.insert('g', 0b11100100101111);
-    map.insert('h', 0b101101111101101);
-    map.insert('i', 0b111010010010111);
-    map.insert('j', 0b111001001101111);
-    map.insert('k', 0b101101110101101);
-    map.insert('l', 0b100100100100111);
-    map.insert('m', 0b111111101101101);
-    map.insert('n', 0b110101101101101);
-    map.insert('o', 0b11101101101110);
-    map.insert('p', 0b111101111100100);
-    map.insert('q', 0b111101101110011);
-    map.insert('r', 0b111101110101101);
-    map.insert('s', 0b11100111001110);
-    map.insert('t', 0b111010010010010);
-    map.insert('u', 0b101101101101111);
-    map.insert('v', 0b101101101101010);
-    map.insert('w', 0b101101101111111);
-    map.insert('x', 0b101101010101101);
-    map.insert('y', 0b101101010010010);
-    map.insert('z', 0b111001010100111);
+    map.insert('A', 0b10101111101101);
+    map.insert('B', 0b110101110101110);
+    map.insert('C', 0b011100100100011);
+    map.insert('D', 0b110101101101110);
+    map.insert('E', 0b111100111100111);
+    map.insert('F', 0b111100110100100);
+    map.insert('G', 0b11100100101111);
+    map.insert('H', 0b101101111101101);
+    map.insert('I', 0b111010010010111);
+    map.insert('J', 0b111001001101111);
+    map.insert('K', 0b101101110101101);
+    map.insert('L', 0b100100100100111);
+    map.insert('M', 0b111111101101101);
+    map.insert('N', 0b110101101101101);
+    map.insert('O', 0b11101101101110);
+    map.insert('P', 0b111101111100100);
+    map.insert('Q', 0b111101101110011);
+    map.insert('R', 0b111101110101101);
+    map.insert('S', 0b11100111001110);
+    map.insert('T', 0b111010010010010);
+    map.insert('U', 0b101101101101111);
+    map.insert('V', 0b101101101101010);
+    map.insert('W', 0b101101101111111);
+    map.insert('X', 0b101101010101101);
+    map.insert('Y', 0b101101010010010);
+    map.insert('Z', 0b111001010100111);
+    map.insert('a', 0b000110011101111);
+    map.insert('b', 0b100100110101110);
+    map.insert('c', 0b000000011100011);
+    map.insert('d', 0b001001011101011);
+    map.insert('e', 0b000010111100011);
+    map.insert('f', 0b001010111010010);
+    map.insert('g', 0b000011101011110);
+    map.insert('h', 0b100100110101101);
+    map.insert('i', 0b010000010010010);
+    map.insert('j', 0b001000001101010);
+    map.insert('k', 0b100101110110101);
+    map.insert('l', 0b100100100100011);
+    map.insert('m', 0b000000111111101);
+    map.insert('n', 0b000000110101101);
+    map.insert('o', 0b000000011101110);
+    map.insert('p', 0b000110101110100);
+    map.insert('q', 0b000011101011001);
+    map.insert('r', 0b000000011100100);
+    map.insert('s', 0b000011100001110);
+    map.insert('t', 0b010111010010011);
+    map.insert('u', 0b000000101101011);
+    map.insert('v', 0b000000101101010);
+    map.insert('w', 0b000000101111111);
+    map.insert('x', 0b000000101010101);
+    map.insert('y', 0b000101011001110);
+    map.insert('z', 0b000111001010111);
     map.insert(' ', 0b0);
     map.insert('-', 0b111000000);
     map.insert('=', 0b111000111000);
@@ -85,24 +111,46 @@ c_singleton!(CHARMAP, HashMap<char, u32>, || {
 c_singleton!(CHARMAP_SPECIAL, HashMap<char, u64>, || {
     let mut map = HashMap::new();
 
-    map.insert('U', 0b0010001110101010010000100);
+    map.insert('↑', 0b0010001110101010010000100);
 
     map
 });
 
 static UNKNOWN_CHAR: &u32 = &31599;
 
+// character codes for chr and ord: ascii keeps its codes, the other glyphs follow from 128 on
+static EXTENDED_CHARS: [char; 6] = ['´', 'ä', 'ö', 'ü', 'ß', '↑'];
+
+pub fn char_to_code(char: char) -> Option<u32> {
+    if char.is_ascii() {
+        Some(char as u32)
+    } else {
+        EXTENDED_CHARS
+            .iter()
+            .position(|c| *c == char)
+            .map(|pos| pos as u32 + 128)
+    }
+}
+
+pub fn code_to_char(code: u32) -> Option<char> {
+    if code < 128 {
+        char::from_u32(code)
+    } else {
+        EXTENDED_CHARS.get(code as usize - 128).copied()
+    }
+}
+
 pub fn get_char_special(char: char) -> u32 {
     *get_s_val!(CHARMAP).get(&char).unwrap_or(UNKNOWN_CHAR)
 }
 
 pub fn put_char_on_canvas(char: char, x: i32, y: i32, color: u8) -> u32 {
-    put_char_on_canvas_custom(char, x, y, color, set_pixel)
+    put_char_on_canvas_custom(char, x, y, color, put_pixel)
 }
 
 pub fn put_char_on_canvas_custom<F>(char: char, x: i32, y: i32, color: u8, set_pixel: F) -> u32
 where
-    F: Fn(i32, i32, u8) -> (),
+    F: Fn(i32, i32, u8),
 {
     if let Some(char) = get_s_val!(CHARMAP).get(&char) {
         print_char(char, x, y, color, set_pixel);
@@ -111,14 +159,14 @@ where
         print_special_char(char, x, y, color, set_pixel);
         8
     } else {
-        print_char(&UNKNOWN_CHAR, x, y, color, set_pixel);
+        print_char(UNKNOWN_CHAR, x, y, color, set_pixel);
         4
     }
 }
 
 fn print_char<F>(char: &u32, x: i32, y: i32, color: u8, set_pixel: F)
 where
-    F: Fn(i32, i32, u8) -> (),
+    F: Fn(i32, i32, u8),
 {
     for oy in 0..5 {
         for ox in 0..3 {
@@ -132,7 +180,7 @@ where
 
 fn print_special_char<F>(char: &u64, x: i32, y: i32, color: u8, set_pixel: F)
 where
-    F: Fn(i32, i32, u8) -> (),
+    F: Fn(i32, i32, u8),
 {
     for oy in 0..5 {
         for ox in 0..5 {
@@ -143,3 +191,32 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{char_to_code, code_to_char};
+
+    #[test]
+    fn ascii_keeps_its_codes() {
+        assert_eq!(char_to_code('A'), Some(65));
+        assert_eq!(char_to_code('a'), Some(97));
+        assert_eq!(code_to_char(32), Some(' '));
+    }
+
+    #[test]
+    fn extended_glyphs_follow_ascii() {
+        assert_eq!(char_to_code('´'), Some(128));
+        assert_eq!(char_to_code('↑'), Some(133));
+        assert_eq!(code_to_char(129), Some('ä'));
+        assert_eq!(code_to_char(134), None);
+        assert_eq!(char_to_code('é'), None);
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for code in 0..134 {
+            let char = code_to_char(code).unwrap();
+            assert_eq!(char_to_code(char), Some(code));
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use crate::charmap::put_char_on_canvas_custom;
 
 pub fn print<T: Into<String>, F>(set_pixel: F, text: T, x: u32, y: u32, color: u8)
-where F: Fn(i32, i32, u8)->()
+where F: Fn(i32, i32, u8)
  {
     let text: String = text.into();
     let bytes: Vec<char> = text.chars().collect();
@@ -18,7 +18,7 @@ where F: Fn(i32, i32, u8)->()
                 cy += 6;
             },
             _ => {
-                cx += put_char_on_canvas_custom(bytes[i], x as i32, y as i32, color, &set_pixel);
+                cx += put_char_on_canvas_custom(bytes[i], cx as i32, cy as i32, color, &set_pixel);
             },
         };
         i += 1;
@@ -2,6 +2,8 @@ use std::fmt::Debug;
 
 use crate::{
     audio::Audio,
+    canvas_functions::Color,
+    font::Font,
     gamestate::GameState,
     image::parse_image,
     utils::{__from_hex, __to_hex},
@@ -13,6 +15,8 @@ enum HeaderType {
     Sfx,
     Images,
     PreviewImage,
+    Font,
+    Palettes,
     Unknown = 255,
 }
 
@@ -23,6 +27,8 @@ impl HeaderType {
             1 => Self::Sfx,
             2 => Self::Images,
             3 => Self::PreviewImage,
+            4 => Self::Font,
+            5 => Self::Palettes,
             0xff => Self::Unknown,
             _ => Self::Script,
         }
@@ -100,6 +106,27 @@ impl MetaHeader {
     }
 }
 
+// 6 hex digits per color
+fn palettes_to_string(palettes: &[[Color; 16]; 4]) -> String {
+    palettes
+        .iter()
+        .flatten()
+        .map(|color| format!("{:06x}", color.to_hex()))
+        .collect()
+}
+
+fn palettes_from_string(str: &str) -> Option<[[Color; 16]; 4]> {
+    if str.len() < 4 * 16 * 6 || !str.is_ascii() {
+        return None;
+    }
+    let mut palettes = [[Color::rgb(0, 0, 0); 16]; 4];
+    for i in 0..4 * 16 {
+        let hex = u32::from_str_radix(&str[i * 6..(i + 1) * 6], 16).ok()?;
+        palettes[i / 16][i % 16] = Color::from_hex(hex);
+    }
+    Some(palettes)
+}
+
 pub fn game_data_to_string(data: &GameState) -> String {
     let script_header = MetaHeader::new(HeaderType::Script, data.code.join("\n"));
     let sfx_header = MetaHeader::new(HeaderType::Sfx, data.audios.map(|f| f.to_string()).join(""));
@@ -118,6 +145,12 @@ pub fn game_data_to_string(data: &GameState) -> String {
     str.push_str(&script_header.string());
     str.push_str(&sfx_header.string());
     str.push_str(&spr_header.string());
+    if let Some(font) = &data.font {
+        str.push_str(&MetaHeader::new(HeaderType::Font, font.to_string()).string());
+    }
+    if let Some(palettes) = &data.palettes {
+        str.push_str(&MetaHeader::new(HeaderType::Palettes, palettes_to_string(palettes)).string());
+    }
 
     str
 }
@@ -141,12 +174,13 @@ pub fn string_to_game_data(str: String, filename: Option<String>) -> Option<Game
 
     let script_header = headers
         .iter()
-        .find(|f| f.typ == HeaderType::Script)
-        .and_then(|h| Some(h.data.clone()))
+        .find(|f| f.typ == HeaderType::Script).map(|h| h.data.clone())
         .unwrap_or(String::new());
     let image_header = headers.iter().find(|f| f.typ == HeaderType::Images);
     let sfx_header = headers.iter().find(|f| f.typ == HeaderType::Sfx);
     let prev_img_header = headers.iter().find(|f| f.typ == HeaderType::PreviewImage);
+    let font_header = headers.iter().find(|f| f.typ == HeaderType::Font);
+    let palettes_header = headers.iter().find(|f| f.typ == HeaderType::Palettes);
 
     let mut gamestate = GameState {
         audios: [Audio::new(); 32],
@@ -158,6 +192,9 @@ pub fn string_to_game_data(str: String, filename: Option<String>) -> Option<Game
         filename,
         image_vec: Vec::new(),
         preview_image: prev_img_header.and_then(|str| parse_image(200, 180, str.data.clone())),
+        preview_colors: None,
+        font: font_header.and_then(|str| Font::from_string(&str.data)),
+        palettes: palettes_header.and_then(|str| palettes_from_string(&str.data)),
     };
 
     for _ in 0..16384 {
@@ -174,8 +211,8 @@ pub fn string_to_game_data(str: String, filename: Option<String>) -> Option<Game
 
     if let Some(images) = image_header {
         let bytes = images.data.as_bytes();
-        for i in 0..16384usize {
-            gamestate.image_vec[i] = __from_hex(bytes[i] as char);
+        for (pixel, byte) in gamestate.image_vec.iter_mut().zip(bytes) {
+            *pixel = __from_hex(*byte as char);
         }
     } else {
         gamestate.image_vec[258] = 12; // x: 2 y: 2
@@ -207,3 +244,33 @@ pub fn load_r16_png(data: Vec<u8>, filename: Option<String>) -> Option<GameState
 
     string_to_game_data(unsafe { String::from_utf8_unchecked(new_data) }, filename)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palettes_string_round_trips() {
+        let mut palettes = [[Color::rgb(0, 0, 0); 16]; 4];
+        for (p, palette) in palettes.iter_mut().enumerate() {
+            for (c, color) in palette.iter_mut().enumerate() {
+                *color = Color::rgb(p as u8 * 60, c as u8 * 16, 0xff - c as u8);
+            }
+        }
+
+        let str = palettes_to_string(&palettes);
+        assert_eq!(str.len(), 4 * 16 * 6);
+        assert_eq!(&str[6..12], "0010fe");
+        let loaded = palettes_from_string(&str).unwrap();
+        for (a, b) in loaded.iter().flatten().zip(palettes.iter().flatten()) {
+            assert_eq!(a.to_hex(), b.to_hex());
+        }
+    }
+
+    #[test]
+    fn broken_palettes_strings_are_rejected() {
+        let str = palettes_to_string(&[[Color::rgb(1, 2, 3); 16]; 4]);
+        assert!(palettes_from_string(&str[..str.len() - 1]).is_none());
+        assert!(palettes_from_string(&str.replacen("01", "zz", 1)).is_none());
+    }
+}
@@ -0,0 +1,165 @@
+use std::{cell::RefCell, fmt::Display};
+
+use crate::{
+    charmap::{char_to_code, put_char_on_canvas_custom},
+    utils::{__from_hex, __to_hex},
+};
+
+// glyphs are up to 8x8 pixels. the font has a slot for every character code (see charmap::char_to_code)
+pub static GLYPH_SIZE: usize = 8;
+pub static GLYPH_COUNT: usize = 256;
+pub static DEFAULT_LINE_HEIGHT: u8 = 6;
+
+#[derive(Clone, Copy, Default)]
+pub struct Glyph {
+    // 0 means the glyph isn't defined and the builtin one is used
+    pub width: u8,
+    // one byte per row, the highest bit is the leftmost pixel
+    pub rows: [u8; 8],
+}
+
+impl Glyph {
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < GLYPH_SIZE && y < GLYPH_SIZE && self.rows[y] & (0x80 >> x) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        if x >= GLYPH_SIZE || y >= GLYPH_SIZE {
+            return;
+        }
+        if value {
+            self.rows[y] |= 0x80 >> x;
+        } else {
+            self.rows[y] &= !(0x80 >> x);
+        }
+    }
+
+    // a copy of the builtin glyph, used as the starting point when editing a glyph
+    pub fn from_builtin(char: char) -> Self {
+        let rows = RefCell::new([0u8; 8]);
+        let width = put_char_on_canvas_custom(char, 0, 0, 1, |x, y, _| {
+            if x >= 0 && y >= 0 && (x as usize) < GLYPH_SIZE && (y as usize) < GLYPH_SIZE {
+                rows.borrow_mut()[y as usize] |= 0x80 >> x;
+            }
+        });
+        let rows = rows.into_inner();
+        Self {
+            width: (width as u8 - 1).min(GLYPH_SIZE as u8),
+            rows,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Font {
+    pub line_height: u8,
+    pub glyphs: Vec<Glyph>,
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Font {
+    pub fn new() -> Self {
+        Self {
+            line_height: DEFAULT_LINE_HEIGHT,
+            glyphs: vec![Glyph::default(); GLYPH_COUNT],
+        }
+    }
+
+    pub fn glyph(&self, char: char) -> Option<&Glyph> {
+        char_to_code(char)
+            .and_then(|code| self.glyphs.get(code as usize))
+            .filter(|glyph| glyph.width > 0)
+    }
+
+    // draws the char if the font defines it and returns how far the cursor has to move
+    pub fn put_char<F>(&self, char: char, x: i32, y: i32, color: u8, set_pixel: F) -> Option<u32>
+    where
+        F: Fn(i32, i32, u8),
+    {
+        let glyph = self.glyph(char)?;
+        for oy in 0..GLYPH_SIZE {
+            for ox in 0..glyph.width as usize {
+                if glyph.get(ox, oy) {
+                    set_pixel(x + ox as i32, y + oy as i32, color);
+                }
+            }
+        }
+        Some(glyph.width as u32 + 1)
+    }
+
+    pub fn from_string(str: &String) -> Option<Self> {
+        let bytes = str.as_bytes();
+        if bytes.len() < 2 + GLYPH_COUNT * 17 {
+            return None;
+        }
+        let byte_at = |i: usize| (__from_hex(bytes[i] as char) << 4) | __from_hex(bytes[i + 1] as char);
+
+        let mut font = Self::new();
+        font.line_height = byte_at(0).max(1);
+        for i in 0..GLYPH_COUNT {
+            let off = 2 + i * 17;
+            let glyph = &mut font.glyphs[i];
+            glyph.width = __from_hex(bytes[off] as char).min(GLYPH_SIZE as u8);
+            for row in 0..GLYPH_SIZE {
+                glyph.rows[row] = byte_at(off + 1 + row * 2);
+            }
+        }
+        Some(font)
+    }
+}
+
+// line height, then width and 8 rows per glyph. every value is stored as hex nibbles
+impl Display for Font {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut str = String::with_capacity(2 + GLYPH_COUNT * 17);
+        str.push(__to_hex(self.line_height >> 4));
+        str.push(__to_hex(self.line_height & 0xf));
+        for glyph in &self.glyphs {
+            str.push(__to_hex(glyph.width));
+            for row in glyph.rows {
+                str.push(__to_hex(row >> 4));
+                str.push(__to_hex(row & 0xf));
+            }
+        }
+        f.write_str(&str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Font, GLYPH_COUNT};
+
+    #[test]
+    fn font_string_round_trips() {
+        let mut font = Font::new();
+        font.line_height = 9;
+        font.glyphs[65].width = 5;
+        font.glyphs[65].set(0, 0, true);
+        font.glyphs[65].set(4, 7, true);
+        font.glyphs[255].width = 8;
+        font.glyphs[255].rows = [0xff, 0x81, 0, 0x10, 0, 0, 0, 0xaa];
+
+        let str = font.to_string();
+        assert_eq!(str.len(), 2 + GLYPH_COUNT * 17);
+        let loaded = Font::from_string(&str).unwrap();
+        assert_eq!(loaded.line_height, 9);
+        for (a, b) in font.glyphs.iter().zip(loaded.glyphs.iter()) {
+            assert_eq!(a.width, b.width);
+            assert_eq!(a.rows, b.rows);
+        }
+        assert!(loaded.glyph('A').unwrap().get(4, 7));
+        assert!(loaded.glyph('B').is_none());
+    }
+
+    #[test]
+    fn short_font_strings_are_rejected() {
+        assert!(Font::from_string(&"06".to_string()).is_none());
+        let str = Font::new().to_string();
+        assert!(Font::from_string(&str[..str.len() - 1].to_string()).is_none());
+    }
+}
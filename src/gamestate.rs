@@ -1,16 +1,28 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use crate::{
-    audio::Audio,
+    audio::{Audio, AUDIO_SIZE},
     c_singleton,
+    canvas_functions::{
+        clear_states, color_index_to_color, fillp, pal, set_screen_mode, window_color_index, Color, PALETTES,
+    },
     file_parser::{game_data_to_string, string_to_game_data, load_r16_png},
+    font::Font,
+    fs::read,
     get_s_val,
     image::Image,
     luastd::setup_stdlib,
-    luautils::{init_ctx, run_function_if_function},
-    memory::{displaymemory, keymemory, sfx},
+    luautils::{init_ctx, reset_watchdog, run_function_if_function, MAIN_CHUNK},
+    memory::{
+        colormemory, getmem, keymemory, memset, peek, sfx, sfxdatamemory, spritememory,
+        usermemory,
+    },
     overlay::overlay::set_overlay,
-    set_s_val, Singleton, TIME,
+    set_s_val, Singleton, CARTSPATH, HEIGHT, RNG, TIME, WIDTH,
 };
-use rlua::{Error, Lua, StdLib, Value};
+use rand::{rngs::StdRng, SeedableRng};
+use rlua::{Context, Error, Lua, MultiValue, StdLib, Value};
 
 pub struct GameState {
     pub code: Vec<String>,
@@ -19,6 +31,11 @@ pub struct GameState {
     pub image_vec: Vec<u8>,
     pub audios: [Audio; 32],
     pub preview_image: Option<Image>,
+    // the colors of the screen when the preview was taken, None for previews loaded from a file
+    pub preview_colors: Option<[Color; 16]>,
+    pub font: Option<Font>,
+    // the cart's own versions of the 4 palettes
+    pub palettes: Option<[[Color; 16]; 4]>,
 }
 
 impl GameState {
@@ -43,6 +60,9 @@ impl GameState {
             image_vec: Vec::with_capacity(16384),
             lua: None,
             preview_image: None,
+            preview_colors: None,
+            font: None,
+            palettes: None,
         };
         for _ in 0..16384usize {
             new.image_vec.push(0);
@@ -58,13 +78,54 @@ impl GameState {
         new
     }
 
+    // the byte of the cart that gets loaded to the address of the memory
+    fn cart_byte(&self, address: u32) -> Option<u8> {
+        let sprites = get_s_val!(spritememory);
+        let sfxdata = get_s_val!(sfxdatamemory);
+        if address >= sprites.start() && address < sprites.start() + sprites.len() {
+            self.image_vec.get((address - sprites.start()) as usize).copied()
+        } else if address >= sfxdata.start() && address < sfxdata.start() + sfxdata.len() {
+            let off = address - sfxdata.start();
+            Some(self.audios[(off / AUDIO_SIZE) as usize].get_byte(off % AUDIO_SIZE))
+        } else {
+            None
+        }
+    }
+
+    fn set_cart_byte(&mut self, address: u32, byte: u8) {
+        let sprites = get_s_val!(spritememory);
+        let sfxdata = get_s_val!(sfxdatamemory);
+        if address >= sprites.start() && address < sprites.start() + sprites.len() {
+            if let Some(pixel) = self.image_vec.get_mut((address - sprites.start()) as usize) {
+                *pixel = byte % 16;
+            }
+        } else if address >= sfxdata.start() && address < sfxdata.start() + sfxdata.len() {
+            let off = address - sfxdata.start();
+            self.audios[(off / AUDIO_SIZE) as usize].set_byte(off % AUDIO_SIZE, byte);
+        }
+    }
+
     fn run_game(&mut self) -> Option<Error> {
         stop_game();
+        reload(None);
+        set_screen_mode(0);
+        fillp(None, None);
+        // the draw and the display palette
+        pal(None, None, None);
         set_s_val!(TIME, 0);
+        // every run starts with a random seed until the cart calls srand
+        set_s_val!(RNG, StdRng::from_entropy());
+        let stack = get_s_val!(INCLUDE_STACK);
+        stack.clear();
+        if let Some(path) = self.filename.as_ref().and_then(|f| Path::new(f).canonicalize().ok()) {
+            stack.push(path);
+        }
         let (res, lua) = init_ctx(StdLib::BASE, |ctx| {
             setup_stdlib(ctx)?;
 
-            ctx.load::<String>(&self.code.join("\n")).exec()?;
+            ctx.load::<String>(&self.code.join("\n"))
+                .set_name(&format!("={}", MAIN_CHUNK))?
+                .exec()?;
 
             if let Some(err) = run_function_if_function(ctx.globals().get("_init").ok(), ctx) {
                 Err(err)
@@ -93,14 +154,180 @@ impl GameState {
     }
 }
 
+// files that are currently being included, starting with the cart itself. used to detect include cycles
+c_singleton!(INCLUDE_STACK, Vec<PathBuf>, Vec::new);
+
+fn include_name(path: &Path) -> String {
+    let root = get_s_val!(CARTSPATH).canonicalize().unwrap_or_default();
+    path.strip_prefix(&root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// looks for `name`, `name.lua` and `name.r16` next to the running cart. nothing outside of CARTSPATH can be included
+fn resolve_include(name: &str) -> Option<PathBuf> {
+    let root = get_s_val!(CARTSPATH).canonicalize().ok()?;
+    let dir = get_path()
+        .and_then(|p| Path::new(&p).parent().map(|p| p.to_path_buf()))
+        .unwrap_or(root.clone());
+
+    for candidate in [
+        name.to_string(),
+        format!("{}.lua", name),
+        format!("{}.r16", name),
+    ] {
+        if let Ok(path) = dir.join(candidate).canonicalize() {
+            if path.is_file() && path.starts_with(&root) {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+// runs a lua file or the code of another cart and returns whatever it returns
+pub fn include<'a>(ctx: Context<'a>, name: String) -> Result<MultiValue<'a>, Error> {
+    let path = resolve_include(&name)
+        .ok_or_else(|| Error::RuntimeError(format!("include: could not find {}", name)))?;
+
+    if get_s_val!(INCLUDE_STACK).contains(&path) {
+        let mut chain: Vec<String> =
+            get_s_val!(INCLUDE_STACK).iter().map(|p| include_name(p)).collect();
+        chain.push(include_name(&path));
+        return Err(Error::RuntimeError(format!(
+            "include: cycle detected: {}",
+            chain.join(" -> ")
+        )));
+    }
+
+    let chunk_name = include_name(&path);
+    // error_line would take the errors of a file called main for errors of the cart
+    if chunk_name == MAIN_CHUNK {
+        return Err(Error::RuntimeError(format!(
+            "include: {} can't be included, {} is the name of the cart's code",
+            name, MAIN_CHUNK
+        )));
+    }
+
+    let data = read(&path)
+        .ok_or_else(|| Error::RuntimeError(format!("include: could not read {}", name)))?;
+    let filename = path.to_str().map(|f| f.to_string());
+    let code = if filename
+        .as_ref()
+        .is_some_and(|f| f.ends_with(".r16") || f.ends_with(".r16.png"))
+    {
+        GameState::load(data, filename)
+            .map(|game| game.code.join("\n"))
+            .ok_or_else(|| Error::RuntimeError(format!("include: {} is not a valid cart", name)))?
+    } else {
+        String::from_utf8(data)
+            .map_err(|_| Error::RuntimeError(format!("include: {} is not a text file", name)))?
+    };
+
+    let chunk_name = format!("={}", chunk_name);
+    get_s_val!(INCLUDE_STACK).push(path);
+    let res = ctx
+        .load(&code)
+        .set_name(&chunk_name)
+        .and_then(|chunk| chunk.call::<(), MultiValue>(()));
+    get_s_val!(INCLUDE_STACK).pop();
+    res
+}
+
 pub fn get_image_vec() -> &'static mut Vec<u8> {
     &mut get_s_val!(GAME_STATE).image_vec
 }
 
+// the ranges of the memory that get loaded from the cart
+fn cart_ranges() -> Vec<(u32, u32, u32)> {
+    [get_s_val!(spritememory), get_s_val!(sfxdatamemory)]
+        .iter()
+        .map(|section| (section.start(), section.start(), section.len()))
+        .collect()
+}
+
+// the range for reload and cstore. src defaults to dst and len to everything up to the end of the sfx.
+// len is clamped so the cart side stays before the end of the sfx and the memory side inside the memory
+fn cart_range(dst: Option<u32>, src: Option<u32>, len: Option<u32>, cart_is_dst: bool) -> Option<(u32, u32, u32)> {
+    let dst = dst?;
+    let src = src.unwrap_or(dst);
+    let (cart, mem) = if cart_is_dst { (dst, src) } else { (src, dst) };
+    let sfxdata = get_s_val!(sfxdatamemory);
+    let cart_len = (sfxdata.start() + sfxdata.len()).saturating_sub(cart);
+    let mem_len = (getmem().len() as u32).saturating_sub(mem);
+    let len = len.unwrap_or(cart_len).min(cart_len).min(mem_len);
+    Some((dst, src, len))
+}
+
+// dst is in the memory, src in the cart
+pub fn reload_range(dst: Option<u32>, src: Option<u32>, len: Option<u32>) -> Option<(u32, u32, u32)> {
+    cart_range(dst, src, len, false)
+}
+
+// dst is in the cart, src in the memory
+pub fn cstore_range(dst: Option<u32>, src: Option<u32>, len: Option<u32>) -> Option<(u32, u32, u32)> {
+    cart_range(dst, src, len, true)
+}
+
+// copies len bytes of the cart at src to dst in the memory. without a range the sprites and sfx get reloaded
+pub fn reload(range: Option<(u32, u32, u32)>) {
+    let state = get_s_val!(GAME_STATE);
+    let mem = getmem();
+    for (dst, src, len) in range.map_or_else(cart_ranges, |range| vec![range]) {
+        for i in 0..len {
+            let (Some(dst), Some(src)) = (dst.checked_add(i), src.checked_add(i)) else {
+                break;
+            };
+            if dst as usize >= mem.len() {
+                break;
+            }
+            if let Some(byte) = state.cart_byte(src) {
+                mem[dst as usize] = byte;
+            }
+        }
+    }
+}
+
+// the opposite of reload: writes len bytes of the memory at src into the loaded cart at dst
+pub fn cstore(range: Option<(u32, u32, u32)>) {
+    let state = get_s_val!(GAME_STATE);
+    for (dst, src, len) in range.map_or_else(cart_ranges, |range| vec![range]) {
+        for i in 0..len {
+            let (Some(dst), Some(src)) = (dst.checked_add(i), src.checked_add(i)) else {
+                break;
+            };
+            state.set_cart_byte(dst, peek(src as usize));
+        }
+    }
+}
+
 pub fn run_game() -> Option<Error> {
     get_s_val!(GAME_STATE).run_game()
 }
 
+// the custom font of the cart, if it has one
+pub fn get_font() -> &'static Option<Font> {
+    &get_s_val!(GAME_STATE).font
+}
+
+// the custom font of the cart. creates an empty one if the cart doesn't have one yet
+pub fn get_font_mut() -> &'static mut Font {
+    get_s_val!(GAME_STATE).font.get_or_insert_with(Font::new)
+}
+
+// the palettes of the cart, if it has its own
+pub fn get_palettes() -> &'static Option<[[Color; 16]; 4]> {
+    &get_s_val!(GAME_STATE).palettes
+}
+
+// the palettes of the cart. starts out as a copy of the builtin palettes
+pub fn get_palettes_mut() -> &'static mut [[Color; 16]; 4] {
+    get_s_val!(GAME_STATE)
+        .palettes
+        .get_or_insert_with(|| PALETTES.map(|palette| *palette))
+}
+
 pub fn get_path() -> Option<String> {
     get_s_val!(GAME_STATE).filename.clone()
 }
@@ -140,19 +367,47 @@ pub fn load_game(code: Vec<u8>, filename: Option<String>) -> bool {
     }
 }
 
+// the game doesn't get updated or drawn until then (in ms since the unix epoch)
+static mut SLEEP_UNTIL: u64 = 0;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |dur| dur.as_millis() as u64)
+}
+
+// pauses the game without blocking the console
+pub fn sleep(ms: u64) {
+    unsafe {
+        SLEEP_UNTIL = now() + ms;
+    }
+}
+
+pub fn is_sleeping() -> bool {
+    unsafe { SLEEP_UNTIL > now() }
+}
+
 pub fn stop_game() {
     set_s_val!(TIME, 0);
+    unsafe {
+        SLEEP_UNTIL = 0;
+    }
     get_s_val!(GAME_STATE).lua = None;
+    clear_states();
     set_overlay(crate::overlay::OverlayType::None);
     let mem = get_s_val!(sfx);
     for i in 0..=102 {
         mem.set_at_addr(i, 0);
     }
     get_s_val!(keymemory).set_at_addr(0x3b, 0);
+    for section in [get_s_val!(usermemory), get_s_val!(colormemory)] {
+        memset(section.start() as usize, 0, section.len() as usize);
+    }
 }
 
 pub fn run_fn(fnname: &str) -> Option<Error> {
     if let Some(lua) = &get_s_val!(GAME_STATE).lua {
+        reset_watchdog();
         lua.context(|ctx| {
             let e = run_function_if_function(ctx.globals().get(fnname).ok(), ctx);
             if let Some(err) = e {
@@ -183,15 +438,24 @@ pub fn set_file_name(new: Option<String>) {
 }
 
 pub fn save_screenshot() {
-    let mut img = Image::new(200, 180);
+    // the preview is always as big as the window, smaller screen modes are scaled like on the screen
+    let mut img = Image::new(WIDTH, HEIGHT);
     let vec = img.as_bytes();
-    let mem = get_s_val!(displaymemory);
-    for i in 0..200 * 180usize {
-        vec[i] = mem.get_at_addr_d(i as u32 + 0x13);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            vec[(y * WIDTH + x) as usize] = window_color_index(x, y).unwrap_or(0);
+        }
     }
-    get_s_val!(GAME_STATE).preview_image = Some(img);
+    let state = get_s_val!(GAME_STATE);
+    state.preview_image = Some(img);
+    // the colors can change before the export, e.g. stopping the cart clears setrgb
+    state.preview_colors = Some(std::array::from_fn(|i| color_index_to_color(i as u8)));
 }
 
 pub fn get_preview_image() -> &'static Option<Image> {
     &get_s_val!(GAME_STATE).preview_image
 }
+
+pub fn get_preview_colors() -> Option<[Color; 16]> {
+    get_s_val!(GAME_STATE).preview_colors
+}
@@ -20,7 +20,7 @@ impl Image {
 
     pub fn put_on_canvas<F>(&self, set_pixel: F, x: i32, y: i32)
     where
-        F: Fn(i32, i32, u8) -> (),
+        F: Fn(i32, i32, u8),
     {
         for oy in 0..self.h {
             for ox in 0..self.w {
@@ -10,15 +10,11 @@ impl Version {
     const fn new(major: u8, minor: u8, path: u32) -> Self {
         Self { major, minor, patch: path }
     }
-
-    pub fn to_string(&self) -> String {
-        format!("v{}.{}.{}", self.major, self.minor, self.patch)
-    }
 }
 
 impl Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.to_string())
+        write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)
     }
 }
 
@@ -1,4 +1,5 @@
 use crate::{
+    canvas_functions::window_to_screen_clamped,
     get_s_val,
     memory::{charpress, keymemory}, system::{Keycode, MouseButton},
 };
@@ -14,7 +15,7 @@ pub fn is_key_down(key: Keycode) -> bool {
         }
     }
 
-    return false;
+    false
 }
 
 pub fn handle_keydown(key: Keycode) {
@@ -193,7 +194,9 @@ pub fn handle_scroll(x: i32) {
     );
 }
 
+// x and y are in window pixels, the cart gets the pixel of its screen mode
 pub fn handle_mousemove(x: u32, y: u32) {
+    let (x, y) = window_to_screen_clamped(x, y);
     let mem = get_s_val!(keymemory);
     mem.set_at_addr_u32(0x33, x);
     mem.set_at_addr_u32(0x37, y);
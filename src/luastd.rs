@@ -1,20 +1,246 @@
-use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::f64::consts::TAU;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rlua::Value::Nil;
-use rlua::{Context, Error, FromLua, Table, Value, StdLib};
+use rlua::{Context, Error, FromLua, Function, Table, Value};
 
-use crate::gamestate::get_audio;
+use crate::charmap::{char_to_code, code_to_char};
+use crate::audio::{Audio, AUDIO_SIZE};
+use crate::gamestate::{cstore, cstore_range, include, reload, reload_range, sleep};
 use crate::keyboard::{button_is_down, button_is_pressed, u8_to_button};
-use crate::luautils::value_to_string;
-use crate::memory::{peek, poke, sfx};
+use crate::luautils::{instruction_usage, value_to_string};
+use crate::memory::{
+    drawstatememory, memcpy, memset, peek, peek2, peek4, poke, poke2, poke4, sfx, sfxdatamemory, spritememory,
+    usermemory, SCREEN_MODE_ADDR,
+};
 use crate::{canvas_functions::*, luautils::add_fn};
-use crate::{get_s_val, RNG, TIME};
+use crate::{get_s_val, set_s_val, RNG, TIME};
+
+// name and argument signature of every function registered in setup_stdlib.
+// used by the syntax highlighter and the code editor's autocompletion
+pub static BUILTINS: &[(&str, &str)] = &[
+    ("sleep", "(ms)"),
+    ("add", "(t, v)"),
+    ("del", "(t, v)"),
+    ("deli", "(t, [i])"),
+    ("count", "(t, [v])"),
+    ("foreach", "(t, f)"),
+    ("all", "(t)"),
+    ("stop", "()"),
+    ("peek", "(addr)"),
+    ("poke", "(addr, val)"),
+    ("peek2", "(addr)"),
+    ("poke2", "(addr, val)"),
+    ("peek4", "(addr)"),
+    ("poke4", "(addr, val)"),
+    ("memcpy", "(dst, src, len)"),
+    ("memset", "(dst, val, len)"),
+    ("reload", "([dst], [src], [len])"),
+    ("cstore", "([dst], [src], [len])"),
+    ("btn", "(b)"),
+    ("btnp", "(b)"),
+    ("setp", "(x, y, c)"),
+    ("pget", "(x, y)"),
+    ("sget", "(x, y)"),
+    ("sset", "(x, y, c)"),
+    ("cls", "([c])"),
+    ("rectfill", "(x, y, w, h, c)"),
+    ("cursor", "([x], [y])"),
+    ("print", "(v, [c], [x], [y])"),
+    ("rect", "(x, y, w, h, c)"),
+    ("ellipse", "(cx, cy, rx, ry, c)"),
+    ("circle", "(cx, cy, r, c)"),
+    ("circfill", "(cx, cy, r, c)"),
+    ("ovalfill", "(cx, cy, rx, ry, c)"),
+    ("trifill", "(x1, y1, x2, y2, x3, y3, c)"),
+    ("poly", "(points, c, [fill])"),
+    ("line", "(x1, y1, x2, y2, c)"),
+    ("camera", "([x], [y])"),
+    ("pal", "([c1], [c2], [p])"),
+    ("palt", "([c], [t])"),
+    ("fillp", "([pattern], [c])"),
+    ("clip", "([x], [y], [w], [h])"),
+    ("screenmode", "([mode])"),
+    ("pushstate", "()"),
+    ("popstate", "()"),
+    ("setpal", "(p)"),
+    ("setrgb", "(c, [r], [g], [b])"),
+    ("sspr", "(x, y, sx, sy, w, h, [dw], [dh], [flip_x], [flip_y])"),
+    ("spr", "(n, x, y, [w], [h], [flip_x], [flip_y])"),
+    ("rspr", "(sx, sy, sw, sh, x, y, angle, [scale])"),
+    ("sfx", "(n)"),
+    ("rnd", "([x])"),
+    ("srand", "(seed)"),
+    ("time", "()"),
+    ("cos", "(x)"),
+    ("sin", "(x)"),
+    ("sqrt", "(x)"),
+    ("flr", "(x)"),
+    ("ceil", "(x)"),
+    ("abs", "(x)"),
+    ("sgn", "(x)"),
+    ("min", "(x, [y])"),
+    ("max", "(x, [y])"),
+    ("mid", "(x, y, z)"),
+    ("tcos", "(t)"),
+    ("tsin", "(t)"),
+    ("atan2", "(dx, dy)"),
+    ("band", "(a, b)"),
+    ("bor", "(a, b)"),
+    ("bxor", "(a, b)"),
+    ("bnot", "(a)"),
+    ("shl", "(a, n)"),
+    ("shr", "(a, n)"),
+    ("sub", "(s, i, [j])"),
+    ("split", "(s, [sep], [convert_numbers])"),
+    ("tostr", "(v, [hex])"),
+    ("tonum", "(s)"),
+    ("chr", "(code)"),
+    ("ord", "(s, [i])"),
+    ("include", "(name)"),
+    ("stat", "(n)"),
+];
+
+// turns lua style indices (1-based, negative counts from the end, both inclusive) into a range
+fn char_range(len: usize, i: i64, j: i64) -> (usize, usize) {
+    let len = len as i64;
+    let to_index = |idx: i64| if idx < 0 { len + idx + 1 } else { idx };
+    let start = to_index(i).max(1);
+    let end = to_index(j).min(len);
+    if start > end {
+        (0, 0)
+    } else {
+        (start as usize - 1, end as usize)
+    }
+}
+
+// parses decimal, 0x hex and 0b binary numbers
+fn parse_number<'a>(str: &str) -> Option<Value<'a>> {
+    let str = str.trim();
+    let (negative, digits) = match str.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, str),
+    };
+    // from_str_radix would take another sign, so only digits are allowed after the prefix
+    let int = |digits: &str, radix: u32| {
+        if digits.chars().all(|c| c.is_digit(radix)) {
+            i64::from_str_radix(digits, radix).ok()
+        } else {
+            None
+        }
+    };
+    let num = if let Some(hex) = digits.strip_prefix("0x") {
+        int(hex, 16)
+    } else if let Some(bin) = digits.strip_prefix("0b") {
+        int(bin, 2)
+    } else if let Some(num) = int(digits, 10) {
+        Some(num)
+    } else {
+        return str.parse::<f64>().ok().filter(|n| n.is_finite()).map(Value::Number);
+    };
+    num.and_then(|num| if negative { num.checked_neg() } else { Some(num) })
+        .map(Value::Integer)
+}
+
+// shifts right for negative n. shr is arithmetic, so it keeps the sign
+fn shift_left(a: i64, n: i64) -> i64 {
+    if n >= 64 {
+        0
+    } else if n >= 0 {
+        a << n
+    } else if n > -64 {
+        a >> -n
+    } else if a < 0 {
+        -1
+    } else {
+        0
+    }
+}
+
+// accepts {x1, y1, x2, y2, ...} as well as {{x1, y1}, {x2, y2}, ...}
+fn table_to_points(table: Table) -> Result<Vec<(i32, i32)>, Error> {
+    let mut points: Vec<(i32, i32)> = Vec::new();
+    if let Value::Table(..) = table.get::<_, Value>(1)? {
+        for point in table.sequence_values::<Table>() {
+            let point = point?;
+            points.push((point.get(1)?, point.get(2)?));
+        }
+    } else {
+        let coords: Vec<i32> = table.sequence_values().collect::<Result<_, _>>()?;
+        for pair in coords.chunks_exact(2) {
+            points.push((pair[0], pair[1]));
+        }
+    }
+    Ok(points)
+}
+
+// removes t[i] and moves the following values down. returns the removed value
+fn remove_at<'a>(table: &Table<'a>, idx: i64) -> Result<Value<'a>, Error> {
+    let len = table.len()?;
+    if idx < 1 || idx > len {
+        return Ok(Nil);
+    }
+    let value: Value = table.get(idx)?;
+    for i in idx..len {
+        table.set(i, table.get::<_, Value>(i + 1)?)?;
+    }
+    table.set(len, Nil)?;
+    Ok(value)
+}
+
+// whether two values are the same for lua's rawequal, through a table key. nil and nan can't be keys
+fn same_value<'a>(ctx: Context<'a>, a: &Value<'a>, b: Value<'a>) -> Result<bool, Error> {
+    if let (Value::Number(a), Value::Number(b)) = (a, &b) {
+        if a.is_nan() {
+            return Ok(b.is_nan());
+        }
+    }
+    let set = ctx.create_table()?;
+    if set.raw_set(a.clone(), true).is_err() {
+        return Ok(matches!(b, Nil));
+    }
+    Ok(set.raw_get::<_, Option<bool>>(b)?.is_some())
+}
+
+// the next non-nil value for all/foreach. `current` is the value returned last, at index `i`. if it's
+// still there the index advances, otherwise it was deleted and the next value moved into its place
+fn next_value<'a>(
+    ctx: Context<'a>,
+    table: &Table<'a>,
+    i: &mut i64,
+    current: &mut Value<'a>,
+) -> Result<Value<'a>, Error> {
+    if *i == 0 || same_value(ctx, current, table.get(*i)?)? {
+        *i += 1;
+    }
+    let len = table.len()?;
+    while *i <= len {
+        let value: Value = table.get(*i)?;
+        if let Nil = value {
+            *i += 1;
+        } else {
+            *current = value.clone();
+            return Ok(value);
+        }
+    }
+    *current = Nil;
+    Ok(Nil)
+}
+
+// the arguments of sspr and spr, the sizes are optional
+type SsprArgs = (i32, i32, u32, u32, u32, u32, Option<u32>, Option<u32>, Option<bool>, Option<bool>);
+type SprArgs = (u32, i32, i32, Option<f64>, Option<f64>, Option<bool>, Option<bool>);
 
 pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
+    // keep the original collectgarbage around for stat, even if the cart overwrites it
+    ctx.set_named_registry_value(
+        "collectgarbage",
+        ctx.globals().get::<_, Function>("collectgarbage")?,
+    )?;
     add_fn(ctx, "sleep", |_, ms: u64| {
-        thread::sleep(Duration::from_millis(ms));
+        sleep(ms);
         Ok(())
     })?;
     add_fn(ctx, "add", |_, (table, value): (Table, Value<'a>)| {
@@ -22,28 +248,97 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
 
         Ok(())
     })?;
-    add_fn(ctx, "del", |_, (table, idx): (Table, i64)| {
-        // let length = table.len()?;
-
-        // let to_pop = length - idx;
-
-        // if to_pop < 0 {
-        //     return Ok(());
-        // }
-
-        // let mut vec: Vec<Value> = Vec::with_capacity(to_pop as usize);
-
-        // for i in 0..to_pop {
-        //     vec.push(table.get(idx + i)?);
-        //     table.set(i, Nil)?;
-        // }
-        // table.set(idx, Nil)?;
-
-        // for i in 0..vec.len() {
-        //     table.set(idx + i as i64, vec[i].clone())?;
-        // }
-
-        Ok(())
+    add_fn(ctx, "del", |ctx, (table, value): (Table, Value)| {
+        // rawequal through a table key. nil and nan can't be keys and are never in a sequence
+        let set = ctx.create_table()?;
+        if set.raw_set(value, true).is_err() {
+            return Ok(Nil);
+        }
+        for i in 1..=table.len()? {
+            if set.raw_get::<_, bool>(table.get::<_, Value>(i)?)? {
+                return remove_at(&table, i);
+            }
+        }
+        Ok(Nil)
+    })?;
+    add_fn(ctx, "deli", |_, (table, idx): (Table, Option<i64>)| {
+        let idx = if let Some(idx) = idx { idx } else { table.len()? };
+        remove_at(&table, idx)
+    })?;
+    add_fn(ctx, "count", |ctx, (table, value): (Table, Option<Value>)| {
+        let set = match value {
+            Some(value) => {
+                let set = ctx.create_table()?;
+                if set.raw_set(value, true).is_err() {
+                    return Ok(0);
+                }
+                Some(set)
+            }
+            None => None,
+        };
+        let mut count = 0;
+        for i in 1..=table.len()? {
+            let v: Value = table.get(i)?;
+            let matches = match &set {
+                Some(set) => set.raw_get::<_, bool>(v)?,
+                None => !matches!(v, Nil),
+            };
+            if matches {
+                count += 1;
+            }
+        }
+        Ok(count)
+    })?;
+    add_fn(ctx, "foreach", |ctx, (table, func): (Table, Function)| {
+        let (mut i, mut current) = (0, Nil);
+        loop {
+            let value = next_value(ctx, &table, &mut i, &mut current)?;
+            if let Nil = value {
+                return Ok(());
+            }
+            func.call::<_, ()>(value)?;
+        }
+    })?;
+    // for v in all(t): the state is {t, i, v} so that deleting the current value doesn't skip the next one
+    ctx.set_named_registry_value(
+        "all_iter",
+        ctx.create_function(|ctx, (state, _): (Table, Value)| {
+            let table: Table = state.get("t")?;
+            let mut i: i64 = state.get("i")?;
+            let mut current: Value = state.get("v")?;
+            let value = next_value(ctx, &table, &mut i, &mut current)?;
+            state.set("i", i)?;
+            state.set("v", current)?;
+            Ok(value)
+        })?,
+    )?;
+    add_fn(ctx, "all", |ctx, table: Table| {
+        let state = ctx.create_table()?;
+        state.set("i", 0)?;
+        state.set("t", table)?;
+        Ok((
+            ctx.named_registry_value::<_, Function>("all_iter")?,
+            state,
+            Nil,
+        ))
+    })?;
+    add_fn(ctx, "include", |ctx, name: String| include(ctx, name))?;
+    // 0: lua memory in kb, 1: fraction of the instruction budget used this frame
+    add_fn(ctx, "stat", |ctx, n: u8| match n {
+        0 => ctx
+            .named_registry_value::<_, Function>("collectgarbage")?
+            .call::<_, f64>("count"),
+        1 => Ok(instruction_usage()),
+        // where the user memory starts and how big it is
+        2 => Ok(get_s_val!(usermemory).start() as f64),
+        3 => Ok(get_s_val!(usermemory).len() as f64),
+        // where the sprites and sfx are, see reload and cstore
+        4 => Ok(get_s_val!(spritememory).start() as f64),
+        5 => Ok(get_s_val!(sfxdatamemory).start() as f64),
+        // the resolution of the screen mode
+        6 => Ok(screen_width() as f64),
+        7 => Ok(screen_height() as f64),
+        _ => Ok(0.0),
     })?;
     add_fn(ctx, "stop", |_, _: ()| {
         Err::<(), Error>(rlua::Error::external("Exit"))
@@ -53,6 +348,40 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
         poke(address as usize, value);
         Ok(())
     })?;
+    add_fn(ctx, "peek2", |_, address: u32| Ok(peek2(address as usize)))?;
+    add_fn(ctx, "poke2", |_, (address, value): (u32, u16)| {
+        poke2(address as usize, value);
+        Ok(())
+    })?;
+    add_fn(ctx, "peek4", |_, address: u32| Ok(peek4(address as usize)))?;
+    add_fn(ctx, "poke4", |_, (address, value): (u32, u32)| {
+        poke4(address as usize, value);
+        Ok(())
+    })?;
+    add_fn(ctx, "memcpy", |_, (dst, src, len): (u32, u32, u32)| {
+        memcpy(dst as usize, src as usize, len as usize);
+        Ok(())
+    })?;
+    add_fn(
+        ctx,
+        "reload",
+        |_, (dst, src, len): (Option<u32>, Option<u32>, Option<u32>)| {
+            reload(reload_range(dst, src, len));
+            Ok(())
+        },
+    )?;
+    add_fn(
+        ctx,
+        "cstore",
+        |_, (dst, src, len): (Option<u32>, Option<u32>, Option<u32>)| {
+            cstore(cstore_range(dst, src, len));
+            Ok(())
+        },
+    )?;
+    add_fn(ctx, "memset", |_, (dst, value, len): (u32, u8, u32)| {
+        memset(dst as usize, value, len as usize);
+        Ok(())
+    })?;
     add_fn(ctx, "btn", |_, button: u8| {
         Ok(button_is_down(u8_to_button(button)))
     })?;
@@ -63,6 +392,12 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
         set_pixel(x, y, color);
         Ok(())
     })?;
+    add_fn(ctx, "pget", |_, (x, y): (i32, i32)| Ok(get_pixel(x, y)))?;
+    add_fn(ctx, "sget", |_, (x, y): (i32, i32)| Ok(sget(x, y)))?;
+    add_fn(ctx, "sset", |_, (x, y, color): (i32, i32, u8)| {
+        sset(x, y, color);
+        Ok(())
+    })?;
     add_fn(ctx, "cls", |_, color: Option<u8>| {
         clear(color);
         Ok(())
@@ -83,7 +418,7 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
         ctx,
         "print",
         |_, (val, col, x, y): (Value, Option<u8>, Option<i32>, Option<i32>)| {
-            print(value_to_string(val).to_lowercase(), x, y, col);
+            print(value_to_string(val), x, y, col);
             Ok(())
         },
     )?;
@@ -107,6 +442,30 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
         circle(cx, cy, r, c);
         Ok(())
     })?;
+    add_fn(ctx, "circfill", |_, (cx, cy, r, c): (i32, i32, i32, u8)| {
+        circfill(cx, cy, r, c);
+        Ok(())
+    })?;
+    add_fn(
+        ctx,
+        "ovalfill",
+        |_, (cx, cy, rx, ry, c): (i32, i32, i32, i32, u8)| {
+            ovalfill(cx, cy, rx, ry, c);
+            Ok(())
+        },
+    )?;
+    add_fn(
+        ctx,
+        "trifill",
+        |_, (x1, y1, x2, y2, x3, y3, c): (i32, i32, i32, i32, i32, i32, u8)| {
+            trifill(x1, y1, x2, y2, x3, y3, c);
+            Ok(())
+        },
+    )?;
+    add_fn(ctx, "poly", |_, (points, c, fill): (Table, u8, Option<bool>)| {
+        poly(&table_to_points(points)?, c, fill.unwrap_or(false));
+        Ok(())
+    })?;
     add_fn(
         ctx,
         "line",
@@ -119,8 +478,8 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
         camera(x, y);
         Ok(())
     })?;
-    add_fn(ctx, "pal", |_, (col1, col2): (Option<u8>, Option<u8>)| {
-        pal(col1, col2);
+    add_fn(ctx, "pal", |_, (col1, col2, p): (Option<u8>, Option<u8>, Option<u8>)| {
+        pal(col1, col2, p);
         Ok(())
     })?;
     add_fn(
@@ -131,6 +490,40 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
             Ok(())
         },
     )?;
+    add_fn(ctx, "fillp", |_, (pattern, color): (Option<i64>, Option<u8>)| {
+        fillp(pattern.map(|p| (p & 0xffff) as u16), color);
+        Ok(())
+    })?;
+    add_fn(
+        ctx,
+        "clip",
+        |_, (x, y, w, h): (Option<i32>, Option<i32>, Option<i32>, Option<i32>)| {
+            clip(x, y, w, h);
+            Ok(())
+        },
+    )?;
+    add_fn(ctx, "screenmode", |_, mode: Option<u8>| {
+        if let Some(mode) = mode {
+            set_screen_mode(mode);
+        }
+        Ok(get_s_val!(drawstatememory).get_at_addr_d(SCREEN_MODE_ADDR))
+    })?;
+    add_fn(ctx, "pushstate", |_, _: ()| {
+        pushstate();
+        Ok(())
+    })?;
+    add_fn(ctx, "popstate", |_, _: ()| {
+        popstate();
+        Ok(())
+    })?;
+    add_fn(
+        ctx,
+        "setrgb",
+        |_, (index, r, g, b): (u8, Option<u8>, Option<u8>, Option<u8>)| {
+            setrgb(index, r.map(|r| (r, g.unwrap_or(0), b.unwrap_or(0))));
+            Ok(())
+        },
+    )?;
     add_fn(ctx, "setpal", |_, palette: u8| {
         switch_palette(palette);
         Ok(())
@@ -138,21 +531,58 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
     add_fn(
         ctx,
         "sspr",
-        |_, (sx, sy, x, y, w, h): (i32, i32, u32, u32, u32, u32)| {
-            sspr(sx, sy, x, y, w, h);
+        |_,
+         (sx, sy, x, y, w, h, dw, dh, flip_x, flip_y): SsprArgs| {
+            sspr(
+                sx,
+                sy,
+                x,
+                y,
+                w,
+                h,
+                dw,
+                dh,
+                flip_x.unwrap_or(false),
+                flip_y.unwrap_or(false),
+            );
 
             Ok(())
         },
     )?;
-    add_fn(ctx, "spr", |_, (idx, x, y): (u32, i32, i32)| {
-        spr(idx, x, y);
+    add_fn(
+        ctx,
+        "spr",
+        |_,
+         (idx, x, y, w, h, flip_x, flip_y): SprArgs| {
+            spr(idx, x, y, w, h, flip_x.unwrap_or(false), flip_y.unwrap_or(false));
 
-        Ok(())
-    })?;
+            Ok(())
+        },
+    )?;
+    add_fn(
+        ctx,
+        "rspr",
+        |_,
+         (sx, sy, sw, sh, x, y, angle, scale): (
+            u32,
+            u32,
+            u32,
+            u32,
+            i32,
+            i32,
+            f64,
+            Option<f64>,
+        )| {
+            rspr(sx, sy, sw, sh, x, y, angle, scale.unwrap_or(1.0));
+
+            Ok(())
+        },
+    )?;
     add_fn(ctx, "sfx", |_, idx: i32| {
         let mem = get_s_val!(sfx);
-        if idx < 32 && idx >= 0 {
-            get_audio(idx as usize).write_to_memory(mem, 0);
+        if (0..32).contains(&idx) {
+            // the sfx data memory, so sfx changed at runtime play too
+            Audio::from_memory(get_s_val!(sfxdatamemory), idx as u32 * AUDIO_SIZE).write_to_memory(mem, 0);
             mem.set_at_addr(102, 1);
             mem.set_at_addr_u32(
                 98,
@@ -184,15 +614,111 @@ pub fn setup_stdlib<'a>(ctx: Context<'a>) -> Result<(), Error> {
             Ok(Value::Number(get_s_val!(RNG).gen_range(0.0f64..=1.0f64)))
         }
     })?;
+    add_fn(ctx, "srand", |_, seed: f64| {
+        set_s_val!(RNG, StdRng::seed_from_u64(seed.to_bits()));
+        Ok(())
+    })?;
     add_fn(ctx, "time", |_, _: ()| Ok(*get_s_val!(TIME)))?;
     add_fn(ctx, "cos", |_, num: f64| Ok(num.cos()))?;
     add_fn(ctx, "sin", |_, num: f64| Ok(num.sin()))?;
     add_fn(ctx, "sqrt", |_, num: f64| Ok(num.sqrt()))?;
     add_fn(ctx, "flr", |_, num: f64| Ok(num.floor()))?;
+    add_fn(ctx, "ceil", |_, num: f64| Ok(num.ceil()))?;
+    add_fn(ctx, "abs", |_, num: f64| Ok(num.abs()))?;
+    add_fn(ctx, "sgn", |_, num: f64| Ok(if num < 0.0 { -1.0 } else { 1.0 }))?;
+    add_fn(ctx, "min", |_, (a, b): (f64, Option<f64>)| Ok(a.min(b.unwrap_or(0.0))))?;
+    add_fn(ctx, "max", |_, (a, b): (f64, Option<f64>)| Ok(a.max(b.unwrap_or(0.0))))?;
+    add_fn(ctx, "mid", |_, (a, b, c): (f64, f64, f64)| {
+        Ok(a.max(b.min(c)).min(b.max(c)))
+    })?;
+    // turn based: 1.0 is a full turn. y points down on the screen, so angles go clockwise
+    add_fn(ctx, "tcos", |_, turns: f64| Ok((turns * TAU).cos()))?;
+    add_fn(ctx, "tsin", |_, turns: f64| Ok(-(turns * TAU).sin()))?;
+    add_fn(ctx, "atan2", |_, (dx, dy): (f64, f64)| {
+        Ok(((-dy).atan2(dx) / TAU).rem_euclid(1.0))
+    })?;
+    add_fn(ctx, "band", |_, (a, b): (i64, i64)| Ok(a & b))?;
+    add_fn(ctx, "bor", |_, (a, b): (i64, i64)| Ok(a | b))?;
+    add_fn(ctx, "bxor", |_, (a, b): (i64, i64)| Ok(a ^ b))?;
+    add_fn(ctx, "bnot", |_, a: i64| Ok(!a))?;
+    add_fn(ctx, "shl", |_, (a, n): (i64, i64)| Ok(shift_left(a, n)))?;
+    add_fn(ctx, "shr", |_, (a, n): (i64, i64)| Ok(shift_left(a, -n)))?;
+
+    // strings are indexed by character, not by byte, so that ä, ö, ü, ... count as one
+    add_fn(ctx, "sub", |_, (str, i, j): (String, i64, Option<i64>)| {
+        let chars: Vec<char> = str.chars().collect();
+        let (start, end) = char_range(chars.len(), i, j.unwrap_or(-1));
+        Ok(chars[start..end].iter().collect::<String>())
+    })?;
+    add_fn(
+        ctx,
+        "split",
+        |ctx, (str, sep, convert): (String, Option<SplitSeparator>, Option<bool>)| {
+            let convert = convert.unwrap_or(true);
+            let chars: Vec<char> = str.chars().collect();
+            let parts: Vec<String> = match sep.unwrap_or(SplitSeparator::Text(",".to_string())) {
+                SplitSeparator::Size(size) => chars
+                    .chunks(size.max(1))
+                    .map(|chunk| chunk.iter().collect())
+                    .collect(),
+                SplitSeparator::Text(sep) if sep.is_empty() => {
+                    chars.iter().map(|c| c.to_string()).collect()
+                }
+                SplitSeparator::Text(sep) => str.split(sep.as_str()).map(|s| s.to_string()).collect(),
+            };
+
+            let table = ctx.create_table()?;
+            for (i, part) in parts.into_iter().enumerate() {
+                let num = if convert { parse_number(&part) } else { None };
+                if let Some(num) = num {
+                    table.set(i + 1, num)?;
+                } else {
+                    table.set(i + 1, part)?;
+                }
+            }
+            Ok(table)
+        },
+    )?;
+    add_fn(ctx, "tostr", |_, (value, hex): (Value, Option<bool>)| {
+        Ok(match value {
+            Value::Integer(num) if hex.unwrap_or(false) => format!("0x{:x}", num),
+            Value::Number(num) if hex.unwrap_or(false) => format!("0x{:x}", num as i64),
+            value => value_to_string(value),
+        })
+    })?;
+    add_fn(ctx, "tonum", |_, value: Value| {
+        Ok(match value {
+            Value::Integer(..) | Value::Number(..) => Some(value),
+            Value::String(str) => str.to_str().ok().and_then(parse_number),
+            _ => None,
+        })
+    })?;
+    add_fn(ctx, "chr", |_, code: u32| {
+        Ok(code_to_char(code).map(|c| c.to_string()))
+    })?;
+    add_fn(ctx, "ord", |_, (str, i): (String, Option<i64>)| {
+        let chars: Vec<char> = str.chars().collect();
+        let (start, end) = char_range(chars.len(), i.unwrap_or(1), i.unwrap_or(1));
+        Ok(if start < end { char_to_code(chars[start]) } else { None })
+    })?;
 
     Ok(())
 }
 
+enum SplitSeparator {
+    Text(String),
+    Size(usize),
+}
+
+impl<'a> FromLua<'a> for SplitSeparator {
+    fn from_lua(lua_value: Value<'a>, lua: Context<'a>) -> rlua::Result<Self> {
+        match lua_value.type_name() {
+            "integer" | "number" => Ok(SplitSeparator::Size(usize::from_lua(lua_value, lua)?)),
+            _ => Ok(SplitSeparator::Text(String::from_lua(lua_value, lua)?)),
+        }
+    }
+}
+
 enum RndArgument<'a> {
     Number(f64),
     Table(Vec<Value<'a>>),
@@ -233,3 +759,83 @@ impl<'a> FromLua<'a> for RndArgument<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rlua::{StdLib, Value};
+
+    use super::{parse_number, setup_stdlib};
+    use crate::gamestate::{cstore_range, reload_range};
+    use crate::luautils::init_ctx;
+    use crate::memory::{getmem, init_test_memory, sfxdatamemory, spritememory, usermemory};
+    use crate::get_s_val;
+
+    // runs the code with the cart functions and returns the string it returns
+    fn run(code: &str) -> String {
+        let (res, _lua) = init_ctx(StdLib::BASE, |ctx| {
+            setup_stdlib(ctx)?;
+            ctx.load(code).eval::<String>()
+        });
+        res.unwrap()
+    }
+
+    #[test]
+    fn deleting_the_current_value_visits_every_value() {
+        let code = "local t, s = {1, 2, 3}, '' foreach(t, function(v) s = s..v del(t, v) end) return s";
+        assert_eq!(run(code), "123");
+        let code = "local t, s = {1, 2, 3}, '' for v in all(t) do s = s..v del(t, v) end return s";
+        assert_eq!(run(code), "123");
+    }
+
+    #[test]
+    fn deleting_a_later_value_doesnt_repeat_the_current_one() {
+        let code = "local t, s = {1, 2, 3, 4}, '' foreach(t, function(v) s = s..v if v == 2 then del(t, 4) end end) return s";
+        assert_eq!(run(code), "123");
+        let code = "local t, s = {1, 2, 3, 4}, '' for v in all(t) do s = s..v if v == 2 then del(t, 4) end end return s";
+        assert_eq!(run(code), "123");
+    }
+
+    #[test]
+    fn adding_and_deleting_in_one_call_skips_nothing() {
+        let code = "local t, s = {1, 2, 3}, '' foreach(t, function(v) s = s..v if v == 1 then add(t, 4) del(t, 1) end end) return s";
+        assert_eq!(run(code), "1234");
+        let code = "local t, s = {1, 2, 3}, '' for v in all(t) do s = s..v if v == 1 then add(t, 4) del(t, 1) end end return s";
+        assert_eq!(run(code), "1234");
+    }
+
+    #[test]
+    fn parse_number_reads_integers_and_floats() {
+        assert!(matches!(parse_number(" 42 "), Some(Value::Integer(42))));
+        assert!(matches!(parse_number("-5"), Some(Value::Integer(-5))));
+        assert!(matches!(parse_number("0x1f"), Some(Value::Integer(31))));
+        assert!(matches!(parse_number("-0b101"), Some(Value::Integer(-5))));
+        assert!(parse_number("-0x8000000000000000").is_none());
+        assert!(matches!(parse_number("1.5"), Some(Value::Number(n)) if n == 1.5));
+        assert!(matches!(parse_number("-2e3"), Some(Value::Number(n)) if n == -2000.0));
+    }
+
+    #[test]
+    fn parse_number_allows_only_one_sign() {
+        for str in ["--5", "-+5", "+-5", "0x-8", "-0x-8000000000000000", "0b+1", "-", "0x", "nan", "inf"] {
+            assert!(parse_number(str).is_none(), "{} should not be a number", str);
+        }
+    }
+
+    #[test]
+    fn cart_ranges_stay_inside_the_cart_and_the_memory() {
+        init_test_memory();
+        let sprites = get_s_val!(spritememory).start();
+        let user = get_s_val!(usermemory).start();
+        let cart_end = get_s_val!(sfxdatamemory).start() + get_s_val!(sfxdatamemory).len();
+        let mem_len = getmem().len() as u32;
+
+        // the default len follows the cart side, which is src for reload and dst for cstore
+        assert_eq!(reload_range(Some(user), Some(sprites), None), Some((user, sprites, cart_end - sprites)));
+        assert_eq!(cstore_range(Some(sprites), Some(user), None), Some((sprites, user, cart_end - sprites)));
+        assert_eq!(reload_range(Some(mem_len - 2), Some(sprites), Some(10)), Some((mem_len - 2, sprites, 2)));
+        assert_eq!(cstore_range(Some(sprites), Some(mem_len - 2), Some(10)), Some((sprites, mem_len - 2, 2)));
+        assert_eq!(reload_range(Some(u32::MAX), Some(0), Some(2)), Some((u32::MAX, 0, 0)));
+
+        assert_eq!(run("reload(0xffffffff, 0, 2) cstore(0xffffffff, 0, 2) return 'ok'"), "ok");
+    }
+}
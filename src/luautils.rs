@@ -1,6 +1,33 @@
-use rlua::{Context, Error, FromLuaMulti, ToLuaMulti};
+use rlua::{Context, Error, FromLuaMulti, HookTriggers, ToLuaMulti};
 use rlua::{FromLua, Function, Lua, MultiValue, Result as LuaResult, StdLib, Value};
 
+use crate::budget::get_limits;
+
+// the watchdog hook runs every HOOK_INTERVAL instructions and counts them against the per-frame budget
+static HOOK_INTERVAL: u32 = 1000;
+static mut INSTRUCTIONS: usize = 0;
+
+// fraction of the instruction budget used in this frame so far
+pub fn instruction_usage() -> f64 {
+    unsafe { INSTRUCTIONS as f64 / get_limits().instructions.max(1) as f64 }
+}
+
+pub fn reset_watchdog() {
+    unsafe {
+        INSTRUCTIONS = 0;
+    }
+}
+
+// name of the chunk the cart's code is loaded as. errors in it look like `main:12: message`
+pub static MAIN_CHUNK: &str = "main";
+
+fn out_of_memory() -> String {
+    format!(
+        "Out of memory: the cart used more than {} kb",
+        get_limits().memory / 1024
+    )
+}
+
 pub fn print_err(err: Error) -> String {
     let msg: String = match err {
         Error::BindError => "too many arguments to function::bind".to_string(),
@@ -10,6 +37,8 @@ pub fn print_err(err: Error) -> String {
         Error::CallbackError { traceback, cause } => {
             if cause.to_string() == "Exit" {
                 "".to_string()
+            } else if let Error::MemoryError(..) = cause.as_ref() {
+                out_of_memory()
             } else {
                 format!("Error: {}\n\n{}", cause, traceback)
             }
@@ -24,7 +53,7 @@ pub fn print_err(err: Error) -> String {
             to,
             message.unwrap_or(String::default())
         ),
-        Error::MemoryError(e) => format!("Out of memory: {}", e),
+        Error::MemoryError(..) => out_of_memory(),
         Error::MismatchedRegistryKey => {
             "A registry key from a different state was used!".to_string()
         }
@@ -56,7 +85,16 @@ pub fn print_err(err: Error) -> String {
     };
 
     eprintln!("{msg}");
-    return format!("{msg}");
+    msg.to_string()
+}
+
+// whether the error was caused by the cart calling `stop()`
+pub fn is_exit(err: &Error) -> bool {
+    match err {
+        Error::CallbackError { cause, .. } => cause.to_string() == "Exit",
+        Error::ExternalError(e) => e.to_string() == "Exit",
+        _ => false,
+    }
 }
 
 pub fn add_fn<'a, A, R, F>(ctx: Context<'a>, name: &str, func: F) -> Result<(), Error>
@@ -83,12 +121,80 @@ pub fn run_function_if_function<'a>(value: Option<Value<'a>>, ctx: Context<'a>)
     })
 }
 
+// compiles the code without running it and returns the error if it doesn't parse
+pub fn check_syntax(lua: &Lua, code: &str) -> Option<Error> {
+    lua.context(|ctx| {
+        ctx.load(code)
+            .set_name(&format!("={}", MAIN_CHUNK))?
+            .into_function()
+            .map(|_| ())
+    })
+    .err()
+}
+
+// returns the (1-based) line number of the first `main:<line>:` in an error message
+pub fn error_line(message: &str) -> Option<usize> {
+    let pattern = format!("{}:", MAIN_CHUNK);
+    for (idx, _) in message.match_indices(&pattern) {
+        // the chunk name has to start at a line, a space or a `]`, `domain:4:` is not the cart
+        let before = message[..idx].chars().next_back();
+        if !before.is_none_or(|c| c == ']' || c.is_whitespace()) {
+            continue;
+        }
+        let rest = &message[idx + pattern.len()..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if !digits.is_empty() && rest[digits.len()..].starts_with(':') {
+            return digits.parse().ok();
+        }
+    }
+    None
+}
+
+// globals of the base library that get removed from every lua state
+static SANDBOX_REMOVED: [&str; 3] = ["dofile", "loadfile", "load"];
+
 pub fn init_ctx<F, R>(lua_mod: StdLib, f: F) -> (R, Lua)
 where
     F: FnOnce(Context) -> R,
 {
     let lua = Lua::new_with(lua_mod);
-    return (lua.context(f), lua);
+    // the base library can run and read files of the host. carts only get include()
+    lua.context(|ctx| {
+        let globals = ctx.globals();
+        for name in SANDBOX_REMOVED {
+            globals.set(name, Value::Nil).expect("could not sandbox the lua state");
+        }
+    });
+    lua.set_memory_limit(Some(get_limits().memory));
+    lua.set_hook(
+        HookTriggers {
+            every_nth_instruction: Some(HOOK_INTERVAL),
+            ..Default::default()
+        },
+        |_, debug| {
+            let budget = get_limits().instructions;
+            unsafe {
+                INSTRUCTIONS += HOOK_INTERVAL as usize;
+                if INSTRUCTIONS <= budget {
+                    return Ok(());
+                }
+            }
+            // the error doesn't get a traceback, so include the location the cart was stopped at
+            let src = debug
+                .source()
+                .short_src
+                .map(|src| String::from_utf8_lossy(src).to_string())
+                .unwrap_or("?".to_string());
+            Err(Error::RuntimeError(format!(
+                "{}:{}: watchdog: the cart ran more than {} instructions in one frame",
+                src,
+                debug.curr_line(),
+                budget
+            )))
+        },
+    );
+    reset_watchdog();
+    (lua.context(f), lua)
 }
 
 pub fn value_to_string(value: Value) -> String {
@@ -108,3 +214,38 @@ pub fn value_to_string(value: Value) -> String {
         _ => "[unknown]".to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rlua::{StdLib, Value};
+
+    use super::{error_line, init_ctx};
+
+    #[test]
+    fn lua_states_cant_read_files() {
+        let (globals, _lua) = init_ctx(StdLib::BASE, |ctx| {
+            ["dofile", "loadfile", "load", "print"]
+                .map(|name| !matches!(ctx.globals().get::<_, Value>(name), Ok(Value::Nil)))
+        });
+        assert_eq!(globals, [false, false, false, true]);
+    }
+
+    #[test]
+    fn error_line_finds_the_first_main_line() {
+        assert_eq!(error_line("main:12: attempt to call a nil value"), Some(12));
+        assert_eq!(
+            error_line("runtime error: main:3: boom\nstack traceback:\n\tmain:5: in function 'f'"),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn error_line_skips_other_chunks() {
+        assert_eq!(error_line("[C]: in ?\nmain:7: in main chunk"), Some(7));
+        assert_eq!(error_line("main: no line here"), None);
+        assert_eq!(error_line("domain:4: not the cart"), None);
+        assert_eq!(error_line("lib/domain:4: boom\n\tmain:9: in main chunk"), Some(9));
+        assert_eq!(error_line("[string \"x\"]main:2: boom"), Some(2));
+        assert_eq!(error_line(""), None);
+    }
+}
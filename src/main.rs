@@ -1,9 +1,11 @@
 pub mod audio;
+pub mod budget;
 pub mod canvas_functions;
 pub mod charmap;
 pub mod custom_canvas_functions;
 pub mod file_parser;
 pub mod frequencies;
+pub mod font;
 pub mod fs;
 pub mod game_handle_key;
 pub mod gamestate;
@@ -36,21 +38,21 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 use canvas_functions::*;
 use dirs::home_dir;
-use gamestate::{draw_game, update_game};
+use gamestate::{draw_game, is_sleeping, update_game};
 use info::{NAME, VERSION};
 use keyboard::{
     handle_acc_keys_down, handle_acc_keys_up, handle_keydown, handle_keyup, handle_mousedown,
     handle_mousemove, handle_mouseup, handle_scroll, keyboard_update, reset_scroll,
 };
 use overlay::{
-    add_line_to_stdout,
+    add_line_to_stdout, show_error,
     overlay::{
         is_overlay_active, ov_handle_keydown, ov_handle_keyup, ov_handle_mousedown,
         ov_handle_mousemove, ov_handle_scroll, renderoverlay, updateoverlay,
     },
 };
-use rand::rngs::ThreadRng;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use singleton::Singleton;
 use std::{
     path::PathBuf,
@@ -67,11 +69,9 @@ use crate::{
 
 #[macro_export]
 macro_rules! swap {
-    ($x: expr, $y: expr) => {{
-        let tmp = $x;
-        $x = $y;
-        $y = tmp;
-    }};
+    ($x: expr, $y: expr) => {
+        std::mem::swap(&mut $x, &mut $y)
+    };
 }
 
 static WIDTH: u32 = 200;
@@ -80,10 +80,10 @@ static HEIGHT: u32 = 180;
 static TIME_STEP_MS: u32 = 1000 / 30;
 
 pub_c_singleton!(TIME, u64, || 0);
-c_singleton!(RNG, ThreadRng, thread_rng);
+c_singleton!(RNG, StdRng, StdRng::from_entropy);
 
 c_singleton!(PATH, PathBuf, || {
-    let mut p = PathBuf::from(home_dir().expect("No homedir found!"));
+    let mut p = home_dir().expect("No homedir found!");
     p.push("rainbow16");
 
     p
@@ -123,7 +123,7 @@ fn real_coordinates_to_pixels(realx: u32, realy: u32, window_sz: (u32, u32)) ->
     let x = realx / mult;
     let y = realy / mult;
 
-    if y > 180 || x > 200 {
+    if y >= HEIGHT || x >= WIDTH {
         None
     } else {
         Some((x, y))
@@ -134,7 +134,7 @@ pub fn main() {
     #[cfg(target_family="wasm")]
     panic::set_hook(Box::new(console_error_panic_hook::hook));
 
-    if let Err(..) = setup_folders() {
+    if setup_folders().is_err() {
         eprintln!("Failed to setup the folders!");
         std::process::exit(1);
     }
@@ -199,7 +199,7 @@ pub fn update(events: Vec<Event>) {
         }
     }
 
-    if get_s_val!(charpress).get_at_addr_u32_d(0) > 0 && keydown_events.len() < 1 {
+    if get_s_val!(charpress).get_at_addr_u32_d(0) > 0 && keydown_events.is_empty() {
         keydown_events.push(Keycode::Unknown);
     }
 
@@ -231,16 +231,21 @@ pub fn update(events: Vec<Event>) {
         keyboard_update();
 
         updateoverlay();
-        if !is_overlay_active() {
-            set_s_val!(TIME, *get_s_val!(TIME) + 1u64);
-            update_game();
+        if !is_overlay_active() && !is_sleeping() {
+            *get_s_val!(TIME) += 1;
+            if let Some(err) = update_game() {
+                show_error(err);
+            }
         }
         reset_scroll();
 
         renderoverlay();
+        // sleep only pauses _update, the cart keeps drawing
         if !is_overlay_active() {
             cursor(None, None);
-            draw_game();
+            if let Some(err) = draw_game() {
+                show_error(err);
+            }
         }
     }
 }
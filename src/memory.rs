@@ -2,9 +2,9 @@
 
 use std::fmt::Display;
 
-use crate::{get_s_val, singleton::Singleton, HEIGHT, WIDTH};
+use crate::{audio::AUDIO_SIZE, get_s_val, singleton::Singleton, HEIGHT, WIDTH};
 
-static mut __MEM: Singleton<Vec<u8>> = Singleton::new(|| <Vec<u8>>::new());
+static mut __MEM: Singleton<Vec<u8>> = Singleton::new(<Vec<u8>>::new);
 
 pub fn getmem() -> &'static mut Vec<u8> {
     unsafe { __MEM.get() }
@@ -36,6 +36,22 @@ impl MemorySection {
         section
     }
 
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    pub fn len(&self) -> u32 {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn get_at_addr(&self, address: u32) -> Option<u8> {
         let mem = getmem();
         if address >= self.length || self.start + address >= mem.len() as u32 {
@@ -49,6 +65,19 @@ impl MemorySection {
         self.get_at_addr(address).unwrap_or(0)
     }
 
+    pub fn get_at_addr_u16_d(&self, address: u32) -> u16 {
+        self.get_at_addr_u16(address).unwrap_or(0)
+    }
+
+    pub fn get_at_addr_u16(&self, address: u32) -> Option<u16> {
+        Some(self.get_at_addr(address)? as u16 | (self.get_at_addr(address + 1)? as u16) << 8)
+    }
+
+    pub fn set_at_addr_u16(&self, address: u32, value: u16) {
+        self.set_at_addr(address, (value & 0xff) as u8);
+        self.set_at_addr(address + 1, (value >> 8) as u8);
+    }
+
     pub fn get_at_addr_u32_d(&self, address: u32) -> u32 {
         self.get_at_addr_u32(address).unwrap_or(0)
     }
@@ -57,7 +86,7 @@ impl MemorySection {
         let mut num: u32 = 0;
         for i in 0..4 {
             if let Some(v) = self.get_at_addr(address + i) {
-                num |= (v as u32) << i * 8;
+                num |= (v as u32) << (i * 8);
             } else {
                 return None;
             }
@@ -93,10 +122,12 @@ impl Display for MemorySection {
 // 0x0-0xf: Color Translations
 // 0x10: Palette
 // 0x11-0x12: Color Translations (transparency)
-// 0x13-end: Display
+// 0x13-0x8cb2: Display, one row after the other with the width of the screen mode
+pub static DISPLAY_OFFSET: u32 = 0x13;
+
 #[allow(non_upper_case_globals)]
 pub static mut displaymemory: Singleton<MemorySection> = Singleton::new(|| {
-    let section = MemorySection::new(WIDTH * HEIGHT + 19, "Display Memory");
+    let section = MemorySection::new(WIDTH * HEIGHT + DISPLAY_OFFSET, "Display Memory");
     for i in 0..16 {
         section.set_at_addr(i, i as u8);
     }
@@ -149,6 +180,50 @@ pub static mut charpress: Singleton<MemorySection> =
 pub static mut sfx: Singleton<MemorySection> =
     Singleton::new(|| MemorySection::new(103, "SFX Memory"));
 
+// 0x0-0x1: Fill pattern (4x4 bits, the highest bit is the top left pixel)
+// 0x2: Fill pattern color (lower 4 bits) used for the set bits if the 5th bit is set
+// 0x3-0x6: Clip rectangle (x1, y1, x2, y2), x2 and y2 are exclusive
+// 0x7: Screen mode, see: crate::canvas_functions::SCREEN_MODES
+// 0x8-0x17: Display palette, applied to the whole screen when it is shown
+pub static FILLP_ADDR: u32 = 0x0;
+pub static FILLP_COLOR_ADDR: u32 = 0x2;
+pub static CLIP_ADDR: u32 = 0x3;
+pub static SCREEN_MODE_ADDR: u32 = 0x7;
+pub static DISPLAY_PALETTE_ADDR: u32 = 0x8;
+
+#[allow(non_upper_case_globals)]
+pub static mut drawstatememory: Singleton<MemorySection> = Singleton::new(|| {
+    let section = MemorySection::new(DISPLAY_PALETTE_ADDR + 16, "Draw State Memory");
+    section.set_at_addr(CLIP_ADDR + 2, WIDTH as u8);
+    section.set_at_addr(CLIP_ADDR + 3, HEIGHT as u8);
+    for i in 0..16 {
+        section.set_at_addr(DISPLAY_PALETTE_ADDR + i, i as u8);
+    }
+    section
+});
+
+// free for the cart to use, cleared when the cart stops. always starts at USER_MEMORY_ADDR,
+// sections added later go after it
+pub static USER_MEMORY_ADDR: u32 = 0x8d72;
+#[allow(non_upper_case_globals)]
+pub static mut usermemory: Singleton<MemorySection> =
+    Singleton::new(|| MemorySection::new(0x4000, "User Memory"));
+
+// the sprite sheet of the running cart, one byte per pixel. loaded from the cart when it starts
+#[allow(non_upper_case_globals)]
+pub static mut spritememory: Singleton<MemorySection> =
+    Singleton::new(|| MemorySection::new(128 * 128, "Sprite Memory"));
+
+// the 32 sfx of the running cart, see: crate::audio::Audio::write_to_memory
+#[allow(non_upper_case_globals)]
+pub static mut sfxdatamemory: Singleton<MemorySection> =
+    Singleton::new(|| MemorySection::new(32 * AUDIO_SIZE, "SFX Data Memory"));
+
+// 4 bytes per color: r, g, b and 1 if they replace the color of the palette. set with setrgb
+#[allow(non_upper_case_globals)]
+pub static mut colormemory: Singleton<MemorySection> =
+    Singleton::new(|| MemorySection::new(16 * 4, "Color Memory"));
+
 pub fn peek(address: usize) -> u8 {
     let mem = getmem();
     if address >= mem.len() {
@@ -158,15 +233,23 @@ pub fn peek(address: usize) -> u8 {
     }
 }
 
-pub fn init_memory_sections() {
-    let sections = vec![
+// every memory section, in the order they are laid out in memory
+pub fn memory_sections() -> Vec<&'static MemorySection> {
+    vec![
         get_s_val!(displaymemory),
         get_s_val!(keymemory),
         get_s_val!(charpress),
         get_s_val!(sfx),
-    ];
+        get_s_val!(drawstatememory),
+        get_s_val!(usermemory),
+        get_s_val!(spritememory),
+        get_s_val!(sfxdatamemory),
+        get_s_val!(colormemory),
+    ]
+}
 
-    for s in sections {
+pub fn init_memory_sections() {
+    for s in memory_sections() {
         println!("{s}");
     }
 }
@@ -179,3 +262,132 @@ pub fn poke(address: usize, value: u8) {
         println!("too big!");
     }
 }
+
+// the section containing address and the address relative to its start
+fn section_at(address: usize) -> Option<(&'static MemorySection, u32)> {
+    let address = u32::try_from(address).ok()?;
+    memory_sections()
+        .into_iter()
+        .find(|s| address >= s.start() && address < s.start() + s.len())
+        .map(|s| (s, address - s.start()))
+}
+
+// little endian. values crossing the end of a section read as 0 and are only partially written
+pub fn peek2(address: usize) -> u16 {
+    section_at(address).map_or(0, |(s, addr)| s.get_at_addr_u16_d(addr))
+}
+
+pub fn peek4(address: usize) -> u32 {
+    section_at(address).map_or(0, |(s, addr)| s.get_at_addr_u32_d(addr))
+}
+
+pub fn poke2(address: usize, value: u16) {
+    if let Some((s, addr)) = section_at(address) {
+        s.set_at_addr_u16(addr, value);
+    }
+}
+
+pub fn poke4(address: usize, value: u32) {
+    if let Some((s, addr)) = section_at(address) {
+        s.set_at_addr_u32(addr, value);
+    }
+}
+
+// copies len bytes, the ranges may overlap. bytes outside of the memory are skipped
+pub fn memcpy(dst: usize, src: usize, len: usize) {
+    let mem = getmem();
+    let len = len.min(mem.len().saturating_sub(src)).min(mem.len().saturating_sub(dst));
+    if len > 0 {
+        mem.copy_within(src..src + len, dst);
+    }
+}
+
+pub fn memset(dst: usize, value: u8, len: usize) {
+    let mem = getmem();
+    let end = dst.saturating_add(len).min(mem.len());
+    if dst < end {
+        mem[dst..end].fill(value);
+    }
+}
+
+// the memory is global, tests share one set of sections
+#[cfg(test)]
+pub fn init_test_memory() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(init_memory_sections);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_addr(off: u32) -> usize {
+        init_test_memory();
+        (get_s_val!(usermemory).start() + off) as usize
+    }
+
+    #[test]
+    fn user_memory_has_a_fixed_address() {
+        init_test_memory();
+        assert_eq!(get_s_val!(usermemory).start(), USER_MEMORY_ADDR);
+    }
+
+    #[test]
+    fn sections_before_the_draw_state_keep_their_addresses() {
+        init_test_memory();
+        assert_eq!(get_s_val!(keymemory).start(), 0x8cb3);
+        assert_eq!(get_s_val!(drawstatememory).start() + get_s_val!(drawstatememory).len(), USER_MEMORY_ADDR);
+    }
+
+    #[test]
+    fn peek4_and_poke4_are_little_endian() {
+        let addr = user_addr(0x100);
+        poke4(addr, 0x12345678);
+        assert_eq!(peek(addr), 0x78);
+        assert_eq!(peek(addr + 3), 0x12);
+        assert_eq!(peek2(addr), 0x5678);
+        assert_eq!(peek2(addr + 2), 0x1234);
+        assert_eq!(peek4(addr), 0x12345678);
+
+        poke2(addr, 0xabcd);
+        assert_eq!(peek(addr), 0xcd);
+        assert_eq!(peek(addr + 1), 0xab);
+    }
+
+    #[test]
+    fn peek4_stops_at_the_end_of_a_section() {
+        let addr = user_addr(0x4000 - 2);
+        poke4(addr, 0xffffffff);
+        assert_eq!(peek4(addr), 0);
+        assert_eq!(peek2(addr), 0xffff);
+        assert_eq!(peek(addr + 2), 0);
+    }
+
+    #[test]
+    fn memcpy_handles_overlapping_ranges() {
+        let addr = user_addr(0x200);
+        for i in 0..8 {
+            poke(addr + i, i as u8 + 1);
+        }
+        // forwards
+        memcpy(addr + 2, addr, 4);
+        let bytes: Vec<u8> = (0..8).map(|i| peek(addr + i)).collect();
+        assert_eq!(bytes, [1, 2, 1, 2, 3, 4, 7, 8]);
+        // backwards
+        memcpy(addr, addr + 2, 4);
+        let bytes: Vec<u8> = (0..8).map(|i| peek(addr + i)).collect();
+        assert_eq!(bytes, [1, 2, 3, 4, 3, 4, 7, 8]);
+    }
+
+    #[test]
+    fn memcpy_clamps_to_the_memory() {
+        let addr = user_addr(0x300);
+        let len = getmem().len();
+        memset(addr, 5, 4);
+        memcpy(len - 2, addr, 4);
+        assert_eq!(peek(len - 1), 5);
+        memcpy(addr, len, 4);
+        assert_eq!(peek(addr), 5);
+        memset(len - 2, 0, 2);
+    }
+}
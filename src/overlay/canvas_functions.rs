@@ -1,8 +1,8 @@
 #![allow(dead_code, unused_variables, non_upper_case_globals)]
 
-use crate::{get_s_val, swap, HEIGHT, WIDTH, charmap::{put_char_on_canvas, put_char_on_canvas_custom}};
+use crate::{get_s_val, swap, HEIGHT, WIDTH, canvas_functions::Color, charmap::put_char_on_canvas_custom};
 
-use super::globals::DISPLAYMEM;
+use super::globals::{DISPLAYMEM, SWATCHES};
 
 #[inline(always)]
 pub fn set_pixel(x: i32, y: i32, color: u8) {
@@ -10,7 +10,7 @@ pub fn set_pixel(x: i32, y: i32, color: u8) {
         return;
     }
 
-    get_s_val!(DISPLAYMEM)[(y * WIDTH as i32 + x).abs() as usize] = color % 16;
+    get_s_val!(DISPLAYMEM)[(y * WIDTH as i32 + x).unsigned_abs() as usize] = color % 16;
 }
 
 fn to_safe_rect(
@@ -75,6 +75,29 @@ pub fn rectfill(x: i32, y: i32, w: i32, h: i32, color: u8) {
     }
 }
 
+// a rectangle in any rgb color. the color gets an index from 16 on, see: SWATCHES
+pub fn rgbrectfill(x: i32, y: i32, w: i32, h: i32, color: Color) {
+    let swatches = get_s_val!(SWATCHES);
+    let index = match swatches.iter().position(|c| c.to_hex() == color.to_hex()) {
+        Some(pos) => pos,
+        None if swatches.len() < 240 => {
+            swatches.push(color);
+            swatches.len() - 1
+        }
+        None => return,
+    } as u8
+        + 16;
+    if let Some((x1, y1, x2, y2)) = to_safe_rect(x, y, x + w, y + h) {
+        for y in y1..y2 {
+            for x in x1..x2 {
+                if in_bounds(x, y) {
+                    get_s_val!(DISPLAYMEM)[(y * WIDTH as i32 + x) as usize] = index;
+                }
+            }
+        }
+    }
+}
+
 pub fn rect(x: i32, y: i32, w: i32, h: i32, color: u8) {
     rectfill(x, y, w, 1, color);
     rectfill(x, y, 1, h, color);
@@ -134,12 +157,12 @@ pub fn line(mut x1: i32, mut y1: i32, x2: i32, y2: i32, color: u8) {
     while x1 != x2 && y1 != y2 {
         e2 = err * 2;
         if e2 > -dy {
-            err = err - dy;
-            x1 = x1 + sx;
+            err -= dy;
+            x1 += sx;
         }
         if e2 < dx {
-            err = err + dx;
-            y1 = y1 + sy;
+            err += dx;
+            y1 += sy;
         }
         if !in_bounds(x1, y1) {
             continue;
@@ -174,7 +197,7 @@ pub fn cursor(x: Option<i32>, y: Option<i32>) {
     }
 }
 
-pub fn print(text: &String, x: Option<i32>, y: Option<i32>, color: Option<u8>) {
+pub fn print(text: &str, x: Option<i32>, y: Option<i32>, color: Option<u8>) {
     let bytes: Vec<char> = text.chars().collect();
 
     if x.is_some() || y.is_some() {
@@ -0,0 +1,309 @@
+use crate::{c_singleton, get_s_val, luastd::BUILTINS, set_s_val, Singleton};
+
+use super::canvas_functions::*;
+
+static KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+// how many characters have to be typed before the popup opens on its own
+static MIN_PREFIX_LEN: usize = 2;
+static MAX_VISIBLE: usize = 6;
+
+pub struct CompletionItem {
+    pub name: String,
+    pub signature: String,
+}
+
+pub struct Completion {
+    pub items: Vec<CompletionItem>,
+    pub selected: usize,
+    pub prefix_start: usize,
+    forced: bool,
+}
+
+c_singleton!(COMPLETION, Option<Completion>, || None);
+
+#[derive(PartialEq)]
+enum Token {
+    Ident(String),
+    Sym(char),
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn tokenize(code: &[String]) -> Vec<Token> {
+    let chars: Vec<char> = code.join("\n").chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '-' && i + 1 < chars.len() && chars[i + 1] == '-' {
+            // comments
+            if i + 3 < chars.len() && chars[i + 2] == '[' && chars[i + 3] == '[' {
+                while i + 1 < chars.len() && !(chars[i] == ']' && chars[i + 1] == ']') {
+                    i += 1;
+                }
+                i += 2;
+            } else {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+        } else if c == '[' && i + 1 < chars.len() && chars[i + 1] == '[' {
+            while i + 1 < chars.len() && !(chars[i] == ']' && chars[i + 1] == ']') {
+                i += 1;
+            }
+            i += 2;
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (is_ident_char(chars[i]) || chars[i] == '.') {
+                i += 1;
+            }
+        } else if is_ident_char(c) {
+            let mut ident = String::new();
+            while i < chars.len() && is_ident_char(chars[i]) {
+                ident.push(chars[i]);
+                i += 1;
+            }
+            tokens.push(Token::Ident(ident));
+        } else {
+            if !c.is_whitespace() {
+                tokens.push(Token::Sym(c));
+            }
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+fn push_name(names: &mut Vec<String>, name: &String) {
+    if !KEYWORDS.contains(&name.as_str()) && !names.contains(name) {
+        names.push(name.clone());
+    }
+}
+
+// collects the names of functions, locals, globals, parameters and loop variables defined in the code
+pub fn collect_names(code: &[String]) -> Vec<String> {
+    let tokens = tokenize(code);
+    let mut names: Vec<String> = Vec::new();
+
+    let ident_at = |i: usize| -> Option<&String> {
+        match tokens.get(i) {
+            Some(Token::Ident(name)) => Some(name),
+            _ => None,
+        }
+    };
+
+    for i in 0..tokens.len() {
+        match ident_at(i).map(|s| s.as_str()) {
+            Some("function") => {
+                let mut j = i + 1;
+                if let Some(name) = ident_at(j) {
+                    push_name(&mut names, name);
+                    // skip the rest of `a.b:c`
+                    while tokens.get(j + 1) == Some(&Token::Sym('.'))
+                        || tokens.get(j + 1) == Some(&Token::Sym(':'))
+                    {
+                        j += 2;
+                    }
+                    j += 1;
+                }
+                // parameters
+                if tokens.get(j) == Some(&Token::Sym('(')) {
+                    j += 1;
+                    while let Some(tok) = tokens.get(j) {
+                        match tok {
+                            Token::Ident(name) => push_name(&mut names, name),
+                            Token::Sym(')') => break,
+                            _ => {}
+                        }
+                        j += 1;
+                    }
+                }
+            }
+            Some("local") | Some("for") => {
+                let mut j = i + 1;
+                while let Some(name) = ident_at(j) {
+                    if name != "function" {
+                        push_name(&mut names, name);
+                    }
+                    if tokens.get(j + 1) != Some(&Token::Sym(',')) {
+                        break;
+                    }
+                    j += 2;
+                }
+            }
+            Some(name) => {
+                // global assignments: `name =` but not `name ==` and not `a.name =`
+                let is_member = i > 0 && tokens[i - 1] == Token::Sym('.');
+                if !is_member
+                    && tokens.get(i + 1) == Some(&Token::Sym('='))
+                    && tokens.get(i + 2) != Some(&Token::Sym('='))
+                    && (i == 0 || tokens[i - 1] != Token::Sym('='))
+                {
+                    push_name(&mut names, &name.to_string());
+                }
+            }
+            None => {}
+        }
+    }
+
+    names
+}
+
+// returns the column where the identifier that ends at `col` starts
+fn prefix_start(line: &str, col: usize) -> usize {
+    let chars: Vec<char> = line.chars().collect();
+    let mut start = col.min(chars.len());
+    while start > 0 && is_ident_char(chars[start - 1]) {
+        start -= 1;
+    }
+    start
+}
+
+// opens or refreshes the completion popup for the cursor position. `forced` opens it even without a prefix
+pub fn update(code: &[String], line: usize, col: usize, forced: bool) {
+    let forced = forced || get_s_val!(COMPLETION).as_ref().is_some_and(|c| c.forced);
+    let is_open = get_s_val!(COMPLETION).is_some();
+    if line >= code.len() {
+        return close();
+    }
+    let start = prefix_start(&code[line], col);
+    let prefix: String = code[line].chars().skip(start).take(col - start).collect();
+
+    if prefix.chars().next().is_some_and(|c| c.is_ascii_digit())
+        || (!forced && !is_open && prefix.len() < MIN_PREFIX_LEN)
+        || (!forced && prefix.is_empty())
+    {
+        return close();
+    }
+
+    let mut items: Vec<CompletionItem> = Vec::new();
+    for (name, signature) in BUILTINS.iter() {
+        if name.starts_with(&prefix) && *name != prefix {
+            items.push(CompletionItem {
+                name: name.to_string(),
+                signature: signature.to_string(),
+            });
+        }
+    }
+    let mut names = collect_names(code);
+    names.sort();
+    for name in names {
+        if name.starts_with(&prefix)
+            && name != prefix
+            && !items.iter().any(|item| item.name == name)
+        {
+            items.push(CompletionItem {
+                name,
+                signature: String::new(),
+            });
+        }
+    }
+
+    if items.is_empty() {
+        return close();
+    }
+
+    let selected = get_s_val!(COMPLETION)
+        .as_ref()
+        .map_or(0, |c| c.selected)
+        .min(items.len() - 1);
+    set_s_val!(
+        COMPLETION,
+        Some(Completion {
+            items,
+            selected,
+            prefix_start: start,
+            forced,
+        })
+    );
+}
+
+pub fn close() {
+    set_s_val!(COMPLETION, None);
+}
+
+pub fn is_open() -> bool {
+    get_s_val!(COMPLETION).is_some()
+}
+
+pub fn move_selection(down: bool) {
+    if let Some(c) = get_s_val!(COMPLETION) {
+        if down {
+            c.selected = (c.selected + 1) % c.items.len();
+        } else if c.selected > 0 {
+            c.selected -= 1;
+        } else {
+            c.selected = c.items.len() - 1;
+        }
+    }
+}
+
+// replaces the prefix in front of `col` with the selected item. returns the new cursor column
+pub fn accept(line: &mut String, col: usize) -> usize {
+    let res = if let Some(c) = get_s_val!(COMPLETION) {
+        let name = &c.items[c.selected].name;
+        let start = c.prefix_start.min(col);
+        line.replace_range(start..col.min(line.len()), name);
+        start + name.len()
+    } else {
+        col
+    };
+    close();
+    res
+}
+
+pub fn render(x: i32, y: i32) {
+    if let Some(c) = get_s_val!(COMPLETION) {
+        let first = if c.selected >= MAX_VISIBLE {
+            c.selected + 1 - MAX_VISIBLE
+        } else {
+            0
+        };
+        let visible = c.items.len().min(MAX_VISIBLE);
+        let width = c.items[first..first + visible]
+            .iter()
+            .map(|item| (item.name.len() + item.signature.len()) as i32 * 4 + 3)
+            .max()
+            .unwrap_or(0)
+            .min(200);
+        let height = visible as i32 * 6 + 1;
+
+        let x = x.min(200 - width).max(0);
+        // open above the cursor if the popup would cover the status bar
+        let y = if y + 6 + height > 173 { y - height - 1 } else { y + 6 };
+
+        rectfill(x, y, width, height, 15);
+        for i in 0..visible {
+            let item = &c.items[first + i];
+            let iy = y + i as i32 * 6 + 1;
+            if first + i == c.selected {
+                rectfill(x, iy - 1, width, 7, 2);
+            }
+            print(&item.name, Some(x + 1), Some(iy), Some(12));
+            if !item.signature.is_empty() {
+                print(
+                    &item.signature,
+                    Some(x + 1 + item.name.len() as i32 * 4),
+                    Some(iy),
+                    Some(13),
+                );
+            }
+        }
+    }
+}
@@ -2,26 +2,72 @@ use std::path::Path;
 
 use super::{
     canvas_functions::*,
+    completion::{
+        accept as accept_completion, close as close_completion, is_open as is_completing,
+        move_selection as move_completion_selection, render as render_completion,
+        update as update_completion,
+    },
     key_utils::keycode_to_character,
     message::{now, set_message},
+    error_screen::show_error,
     overlay::hide_overlay,
     syntax_parser::{highlight_code, print_highlighted_code, SyntaxToken},
-    terminal::add_line_to_stdout
 };
 use crate::{
+    budget::{exceeded_limits, get_limits, get_usage, refresh as refresh_usage},
     c_singleton,
     gamestate::{get_code, get_path, run_game, set_code as __set_code, gamedata_to_string},
     get_s_val,
-    luautils::print_err,
+    luautils::{check_syntax, error_line, MAIN_CHUNK},
     set_s_val,
     utils::{is_alt_pressed, is_ctrl_pressed, is_shift_pressed},
     system::{copy_to_clipboard, read_clipboard},
     Singleton, system::{MouseButton, Keycode}, fs::write,
 };
+use rlua::{Error, Lua, StdLib};
 
 fn set_code(code: &Vec<String>) {
     __set_code(code);
     highlight();
+    unsafe {
+        LAST_EDIT = now();
+        NEEDS_CHECK = true;
+    }
+}
+
+// the code is only checked once no key was pressed for this many ms
+static CHECK_DELAY_MS: u64 = 300;
+
+static mut LAST_EDIT: u64 = 0;
+static mut NEEDS_CHECK: bool = false;
+
+c_singleton!(SYNTAX_CHECKER, Lua, || Lua::new_with(StdLib::BASE));
+// (0-based line, message) of the current syntax error
+c_singleton!(SYNTAX_ERROR, Option<(usize, String)>, || None);
+
+fn check_code() {
+    unsafe {
+        NEEDS_CHECK = false;
+    }
+    refresh_usage();
+    let error = check_syntax(get_s_val!(SYNTAX_CHECKER), &get_s_val!(CODE).join("\n"));
+    set_s_val!(
+        SYNTAX_ERROR,
+        match error {
+            Some(Error::SyntaxError { message, .. }) => {
+                let line = error_line(&message).unwrap_or(1).max(1) - 1;
+                // strip the `main:<line>: ` in front of the message
+                let prefix = format!("{}:{}: ", MAIN_CHUNK, line + 1);
+                let message = message.strip_prefix(&prefix).unwrap_or(&message);
+                Some((line, message.to_lowercase()))
+            }
+            _ => None,
+        }
+    );
+}
+
+fn gutter_width() -> i32 {
+    get_s_val!(CODE).len().to_string().len() as i32 * 4 + 3
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -48,7 +94,7 @@ fn has_selection() -> bool {
     start.line != end.line || start.col != end.col
 }
 
-c_singleton!(CODE, Vec<String>, || Vec::new());
+c_singleton!(CODE, Vec<String>, Vec::new);
 c_singleton!(START_SEL, SelectionCoordinate, SelectionCoordinate::new);
 c_singleton!(END_SEL, SelectionCoordinate, SelectionCoordinate::new);
 
@@ -59,14 +105,8 @@ fn reset_selection(left: bool) {
 
     let mut start = get_s_val!(START_SEL);
     let mut end = get_s_val!(END_SEL);
-    if start.line > end.line {
-        let tmp = start;
-        start = end;
-        end = tmp;
-    } else if start.line == end.line && end.col < start.col {
-        let tmp = start;
-        start = end;
-        end = tmp;
+    if start.line > end.line || (start.line == end.line && end.col < start.col) {
+        std::mem::swap(&mut start, &mut end);
     }
 
     unsafe {
@@ -87,8 +127,27 @@ static mut LINE: usize = 0;
 static mut COL: usize = 0;
 
 pub fn init() {
+    close_completion();
     set_s_val!(CODE, get_code());
     highlight();
+    check_code();
+}
+
+pub fn goto_line(line: usize) {
+    close_completion();
+    get_s_val!(START_SEL).reset();
+    get_s_val!(END_SEL).reset();
+    unsafe {
+        LINE = line;
+        COL = 0;
+        ensure_inbounds();
+    }
+}
+
+pub fn update() {
+    if unsafe { NEEDS_CHECK && now() - LAST_EDIT > CHECK_DELAY_MS } {
+        check_code();
+    }
 }
 
 pub fn render() {
@@ -133,22 +192,55 @@ unsafe fn _render() {
     if off + SCROLL + 12 > 173 {
         SCROLL = 173 - off - 13;
     }
+    let gutter = gutter_width();
+    let error = get_s_val!(SYNTAX_ERROR);
     if now() % 1000 < 500 && !has_selection() {
-        rectfill(COL as i32 * 4 - 1, LINE as i32 * 6 + SCROLL + 7, 5, 5, 2);
+        rectfill(gutter + COL as i32 * 4 - 1, LINE as i32 * 6 + SCROLL + 7, 5, 5, 2);
     }
     if let Some(code) = get_s_val!(HIGHLIGHTED_CODE) {
         print_highlighted_code(
             code,
-            0,
+            gutter,
             SCROLL + 7,
             get_s_val!(START_SEL),
             get_s_val!(END_SEL),
         );
     } else {
-        for i in 0..code.len() {
-            print(&code[i], Some(0), Some(i as i32 * 6 + SCROLL + 7), None);
+        for (i, line) in code.iter().enumerate() {
+            print(line, Some(gutter), Some(i as i32 * 6 + SCROLL + 7), None);
+        }
+    }
+
+    // line numbers
+    rectfill(0, 7, gutter - 2, 166, 15);
+    for i in 0..code.len() {
+        let y = i as i32 * 6 + SCROLL + 7;
+        if !(1..=173).contains(&y) {
+            continue;
+        }
+        let is_error = error.as_ref().is_some_and(|(line, _)| *line == i);
+        if is_error {
+            rectfill(0, y - 1, gutter - 2, 7, 2);
+        }
+        print(
+            &(i + 1).to_string(),
+            Some(1),
+            Some(y),
+            Some(if is_error || i == LINE { 12 } else { 14 }),
+        );
+    }
+
+    // syntax error
+    if let Some((line, message)) = error {
+        let y = *line as i32 * 6 + SCROLL + 7;
+        let width = (message.len() as i32 * 4 + 3).min(200 - gutter);
+        rectfill(gutter, y + 5, width, 1, 2);
+        if *line != LINE || !is_completing() {
+            rectfill(gutter, y + 6, width, 7, 2);
+            print(message, Some(gutter + 1), Some(y + 7), Some(12));
         }
     }
+
     rectfill(0, 173, 200, 7, 2);
     print(
         &format!("line {} col {}", LINE, COL),
@@ -156,6 +248,22 @@ unsafe fn _render() {
         Some(174),
         None,
     );
+    let usage = get_usage();
+    let limits = get_limits();
+    let usage_str = format!(
+        "tk {}/{} ch {} {}%",
+        usage.tokens,
+        limits.tokens,
+        usage.chars,
+        usage.size * 100 / limits.size.max(1)
+    );
+    print(
+        &usage_str,
+        Some(199 - usage_str.len() as i32 * 4),
+        Some(174),
+        Some(if !exceeded_limits(&usage).is_empty() { 10 } else { 12 }),
+    );
+    render_completion(gutter + COL as i32 * 4, LINE as i32 * 6 + SCROLL + 7);
 }
 
 fn remove_selection() {
@@ -165,14 +273,8 @@ fn remove_selection() {
 
     let mut start = get_s_val!(START_SEL);
     let mut end = get_s_val!(END_SEL);
-    if start.line > end.line {
-        let tmp = start;
-        start = end;
-        end = tmp;
-    } else if start.line == end.line && end.col < start.col {
-        let tmp = start;
-        start = end;
-        end = tmp;
+    if start.line > end.line || (start.line == end.line && end.col < start.col) {
+        std::mem::swap(&mut start, &mut end);
     }
     let code = get_s_val!(CODE);
 
@@ -190,7 +292,7 @@ fn remove_selection() {
         code[start.line as usize].replace_range(start.col as usize..end.col as usize, "");
     } else {
         let str1 = &mut code[start.line as usize];
-        if str1.len() > 0 {
+        if !str1.is_empty() {
             if start.col >= str1.len() as u32 {
                 start.col = str1.len() as u32 - 1;
             }
@@ -200,7 +302,7 @@ fn remove_selection() {
         }
         if code.len() > start.line as usize + 1 {
             let str2 = &mut code[start.line as usize + 1];
-            if str2.len() > 0 {
+            if !str2.is_empty() {
                 if end.col > str2.len() as u32 {
                     end.col = str2.len() as u32;
                 }
@@ -226,26 +328,10 @@ pub fn handle_key(key: Keycode) {
             match key {
                 Keycode::R => {
                     set_code(code);
+                    hide_overlay();
                     if let Some(err) = run_game() {
-                        let err = print_err(err);
-                        let str = err.as_bytes();
-                        let mut vec: Vec<String> = Vec::new();
-
-                        let mut len: usize = 0;
-
-                        for i in 0..str.len() {
-                            if i % 50 == 0 {
-                                vec.push(String::new());
-                                len += 1;
-                            }
-                            vec[len - 1].push(str[i] as char);
-                        }
-
-                        for v in vec {
-                            add_line_to_stdout(v);
-                        }
+                        show_error(err);
                     }
-                    hide_overlay();
                 }
                 Keycode::S => save(),
                 Keycode::V => {
@@ -272,16 +358,10 @@ pub fn handle_key(key: Keycode) {
                     if !has_selection() {
                         str = code[LINE].clone();
                     } else {
-                        let mut start = get_s_val!(START_SEL);
-                        let mut end = get_s_val!(END_SEL);
-                        if start.line > end.line {
-                            let tmp = start;
-                            start = end;
-                            end = tmp;
-                        } else if start.line == end.line && end.col < start.col {
-                            let tmp = start;
-                            start = end;
-                            end = tmp;
+                        let mut start = START_SEL.get();
+                        let mut end = END_SEL.get();
+                        if start.line > end.line || (start.line == end.line && end.col < start.col) {
+                            std::mem::swap(&mut start, &mut end);
                         }
 
                         if start.line == end.line {
@@ -298,9 +378,13 @@ pub fn handle_key(key: Keycode) {
                     }
                     copy_to_clipboard(str.as_str());
                 }
+                Keycode::Space => {
+                    reset_selection(false);
+                    update_completion(code, LINE, COL, true);
+                }
                 Keycode::A => {
-                    let start = get_s_val!(START_SEL);
-                    let end = get_s_val!(END_SEL);
+                    let start = START_SEL.get();
+                    let end = END_SEL.get();
                     start.col = 0;
                     start.line = 0;
                     end.col = code[code.len() - 1].len() as u32;
@@ -310,6 +394,20 @@ pub fn handle_key(key: Keycode) {
             }
             return;
         }
+        if is_completing() {
+            match key {
+                Keycode::Up | Keycode::Down => {
+                    move_completion_selection(key == Keycode::Down);
+                    return;
+                }
+                Keycode::Return | Keycode::Tab => {
+                    COL = accept_completion(&mut code[LINE], COL);
+                    set_code(code);
+                    return;
+                }
+                _ => {}
+            }
+        }
         if let Some(char) = keycode_to_character(Some(key)) {
             if char.len_utf8() > 1 {
                 return;
@@ -325,19 +423,24 @@ pub fn handle_key(key: Keycode) {
                 };
                 LINE += 1;
                 COL = 0;
-                code.insert(LINE, String::from_utf8(remaining).unwrap_or(String::new()));
+                code.insert(LINE, String::from_utf8(remaining).unwrap_or_default());
+                close_completion();
             } else {
                 code[LINE].as_mut_vec().insert(COL, char as u8);
                 COL += 1;
+                update_completion(code, LINE, COL, false);
             }
             set_code(code);
             return;
         }
+        if key != Keycode::Backspace {
+            close_completion();
+        }
 
         match key {
             Keycode::Up => {
                 if !has_selection() && is_shift_pressed() {
-                    let s = get_s_val!(START_SEL);
+                    let s = START_SEL.get();
                     s.col = COL as u32;
                     s.line = LINE as u32;
                 }
@@ -350,36 +453,36 @@ pub fn handle_key(key: Keycode) {
                     COL = 0;
                 }
                 if is_shift_pressed() {
-                    get_s_val!(END_SEL).line = LINE as u32;
-                    get_s_val!(END_SEL).col = COL as u32;
+                    END_SEL.get().line = LINE as u32;
+                    END_SEL.get().col = COL as u32;
                 }
                 ensure_inbounds();
             }
             Keycode::Down => {
                 if !has_selection() && is_shift_pressed() {
-                    let s = get_s_val!(START_SEL);
+                    let s = START_SEL.get();
                     s.col = COL as u32;
                     s.line = LINE as u32;
                 }
                 if !is_shift_pressed() {
                     reset_selection(false);
                 }
-                if code.len() > 0 {
+                if !code.is_empty() {
                     if LINE < code.len() - 1 {
                         LINE += 1;
                     } else {
                         COL = code[LINE].len();
                     }
                     if is_shift_pressed() {
-                        get_s_val!(END_SEL).line = LINE as u32;
-                        get_s_val!(END_SEL).col = COL as u32;
+                        END_SEL.get().line = LINE as u32;
+                        END_SEL.get().col = COL as u32;
                     }
                     ensure_inbounds();
                 }
             }
             Keycode::Left => {
                 if !has_selection() && is_shift_pressed() {
-                    let s = get_s_val!(START_SEL);
+                    let s = START_SEL.get();
                     s.col = COL as u32;
                     s.line = LINE as u32;
                 }
@@ -393,7 +496,7 @@ pub fn handle_key(key: Keycode) {
                     COL = code[LINE].len();
                 }
                 if is_shift_pressed() {
-                    let s = get_s_val!(END_SEL);
+                    let s = END_SEL.get();
                     s.col = COL as u32;
                     s.line = LINE as u32;
                 }
@@ -401,7 +504,7 @@ pub fn handle_key(key: Keycode) {
             Keycode::Right => {
                 let max = code[LINE].len();
                 if !has_selection() && is_shift_pressed() {
-                    let s = get_s_val!(START_SEL);
+                    let s = START_SEL.get();
                     s.col = COL as u32;
                     s.line = LINE as u32;
                 }
@@ -415,7 +518,7 @@ pub fn handle_key(key: Keycode) {
                     COL = 0;
                 }
                 if is_shift_pressed() {
-                    let s = get_s_val!(END_SEL);
+                    let s = END_SEL.get();
                     s.col = COL as u32;
                     s.line = LINE as u32;
                 }
@@ -441,6 +544,9 @@ pub fn handle_key(key: Keycode) {
                         COL -= 1;
                     }
                 }
+                if is_completing() {
+                    update_completion(code, LINE, COL, false);
+                }
                 set_code(code);
             }
             Keycode::Delete => {
@@ -468,8 +574,11 @@ pub fn handle_key(key: Keycode) {
 pub fn save() {
     if let Some(path) = get_path() {
         let code = gamedata_to_string();
-        if let Err(..) = write(&Path::new(&path).to_path_buf(), code.as_bytes()) {
+        let exceeded = exceeded_limits(&refresh_usage());
+        if write(&Path::new(&path).to_path_buf(), code.as_bytes()).is_err() {
             set_message("saving failed");
+        } else if let Some(msg) = exceeded.first() {
+            set_message(&format!("saved, but {}", msg));
         } else {
             set_message("file saved");
         }
@@ -479,9 +588,10 @@ pub fn save() {
 }
 
 pub fn handle_scroll(dy: i32) {
+    close_completion();
     unsafe {
         reset_selection(dy < 0);
-        if (dy > 0 && LINE > 0) || (dy < 0 && LINE < get_s_val!(CODE).len() - 1) {
+        if (dy > 0 && LINE > 0) || (dy < 0 && LINE < CODE.get().len() - 1) {
             LINE = (LINE as i32 - dy) as usize;
         }
     }
@@ -489,11 +599,13 @@ pub fn handle_scroll(dy: i32) {
 
 pub fn handle_mousedown(btn: MouseButton, x: u32, y: u32) {
     if y > 7 && y < 173 && btn == MouseButton::Left {
+        close_completion();
         get_s_val!(START_SEL).reset();
         get_s_val!(END_SEL).reset();
         unsafe {
-            COL = x as usize / 4;
-            LINE = (y as usize - 7) / 6;
+            COL = (x as i32 - gutter_width()).max(0) as usize / 4;
+            LINE = ((y as i32 - 7 - SCROLL) / 6).max(0) as usize;
+            ensure_inbounds();
         }
     }
 }
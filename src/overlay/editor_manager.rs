@@ -1,8 +1,14 @@
+use std::fmt::Display;
+
+use super::completion::{close as close_completion, is_open as is_completing};
 use super::editor::{
     handle_key as handle_key_code, handle_mousedown as handle_mousedown_code,
     handle_scroll as handle_scroll_code, init as init_code, render as render_code,
+    update as update_code,
 };
+use super::font::{render as render_font, keydown as keydown_font, mousedown as mousedown_font, mousemove as mousemove_font};
 use super::overlay::hide_overlay;
+use super::palette::{render as render_palette, keydown as keydown_palette, mousedown as mousedown_palette, mousemove as mousemove_palette};
 use super::spr::{render as render_spr, keydown as keydown_spr, mousedown as mousedown_spr, mousemove as handle_mousemove_spr};
 use super::sfx::{render as render_sfx, mousedown as mousedown_sfx, mousemove as mousemove_sfx, keydown as keydown_sfx};
 
@@ -52,6 +58,30 @@ pub_c_singleton!(IMAGE_SPREDIT_SEL, Image, || parse_image(
     "333333333333ccc33333c3c3c3333ccccc3333ccccc3333c3c3c33333333333".to_string()
 )
 .unwrap());
+pub_c_singleton!(IMAGE_FONTEDIT, Image, || parse_image(
+    9,
+    7,
+    "2222222222222c2222222c2c222222ccc222222c2c222222c2c222222222222".to_string()
+)
+.unwrap());
+pub_c_singleton!(IMAGE_FONTEDIT_SEL, Image, || parse_image(
+    9,
+    7,
+    "3333333333333c3333333c3c333333ccc333333c3c333333c3c333333333333".to_string()
+)
+.unwrap());
+pub_c_singleton!(IMAGE_PALEDIT, Image, || parse_image(
+    9,
+    7,
+    "2222222222c2c2c2c22222222222c2c2c2c22222222222c2c2c2c2222222222".to_string()
+)
+.unwrap());
+pub_c_singleton!(IMAGE_PALEDIT_SEL, Image, || parse_image(
+    9,
+    7,
+    "3333333333c3c3c3c33333333333c3c3c3c33333333333c3c3c3c3333333333".to_string()
+)
+.unwrap());
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum Editor {
@@ -59,20 +89,23 @@ pub enum Editor {
     Code,
     Sfx,
     Sprites,
+    Font,
+    Palette,
 }
 
-impl Editor {
-    fn to_string(&self) -> String {
-        match self {
+impl Display for Editor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
             Editor::Code => "code editor",
             Editor::Sfx => "sounds editor",
             Editor::Sprites => "sprite editor",
-        }
-        .to_string()
+            Editor::Font => "font editor",
+            Editor::Palette => "palette editor",
+        })
     }
 }
 
-pub_c_singleton!(CURRENT_EDITOR, Editor, || Editor::default());
+pub_c_singleton!(CURRENT_EDITOR, Editor, Editor::default);
 
 fn render_titlebar() {
     let cur_sel = get_s_val!(CURRENT_EDITOR);
@@ -94,6 +127,16 @@ fn render_titlebar() {
     } else {
         get_s_val!(IMAGE_SPREDIT).put_on_canvas(set_pixel, 170, 0);
     }
+    if cur_sel == &Editor::Font {
+        get_s_val!(IMAGE_FONTEDIT_SEL).put_on_canvas(set_pixel, 160, 0);
+    } else {
+        get_s_val!(IMAGE_FONTEDIT).put_on_canvas(set_pixel, 160, 0);
+    }
+    if cur_sel == &Editor::Palette {
+        get_s_val!(IMAGE_PALEDIT_SEL).put_on_canvas(set_pixel, 150, 0);
+    } else {
+        get_s_val!(IMAGE_PALEDIT).put_on_canvas(set_pixel, 150, 0);
+    }
 }
 
 pub fn handle_mousedown(button: MouseButton, x: u32, y: u32) {
@@ -104,25 +147,34 @@ pub fn handle_mousedown(button: MouseButton, x: u32, y: u32) {
             set_s_val!(CURRENT_EDITOR, Editor::Sfx);
         } else if x >= 170 {
             set_s_val!(CURRENT_EDITOR, Editor::Sprites);
+        } else if x >= 160 {
+            set_s_val!(CURRENT_EDITOR, Editor::Font);
+        } else if x >= 150 {
+            set_s_val!(CURRENT_EDITOR, Editor::Palette);
         }
     } else {
         match get_s_val!(CURRENT_EDITOR) {
             Editor::Code => handle_mousedown_code(button, x, y),
             Editor::Sprites => mousedown_spr(button, x, y),
             Editor::Sfx => mousedown_sfx(button, x, y),
+            Editor::Font => mousedown_font(button, x, y),
+            Editor::Palette => mousedown_palette(button, x, y),
         }
     }
 }
 
 pub fn handle_scroll(dy: i32) {
-    match get_s_val!(CURRENT_EDITOR) {
-        Editor::Code => handle_scroll_code(dy),
-        _ => {}
+    if *get_s_val!(CURRENT_EDITOR) == Editor::Code {
+        handle_scroll_code(dy);
     }
 }
 
 pub fn handle_key(key: Keycode) {
     if key == Keycode::Escape {
+        if get_s_val!(CURRENT_EDITOR) == &Editor::Code && is_completing() {
+            close_completion();
+            return;
+        }
         hide_overlay();
         return;
     }
@@ -130,6 +182,8 @@ pub fn handle_key(key: Keycode) {
         Editor::Code => handle_key_code(key),
         Editor::Sprites => keydown_spr(key),
         Editor::Sfx => keydown_sfx(key),
+        Editor::Font => keydown_font(key),
+        Editor::Palette => keydown_palette(key),
     }
 }
 
@@ -138,13 +192,15 @@ pub fn render() {
         Editor::Code => render_code(),
         Editor::Sprites => render_spr(),
         Editor::Sfx => render_sfx(),
+        Editor::Font => render_font(),
+        Editor::Palette => render_palette(),
     }
     render_titlebar();
 }
 
 pub fn update() {
-    match get_s_val!(CURRENT_EDITOR) {
-        _ => {}
+    if *get_s_val!(CURRENT_EDITOR) == Editor::Code {
+        update_code();
     }
 }
 
@@ -157,6 +213,8 @@ pub fn handle_mousemove(x: u32, y: u32) {
     match get_s_val!(CURRENT_EDITOR) {
         Editor::Sprites => handle_mousemove_spr(x, y),
         Editor::Sfx => mousemove_sfx(x, y),
+        Editor::Font => mousemove_font(x, y),
+        Editor::Palette => mousemove_palette(x, y),
         _ => {}
     };
 }
\ No newline at end of file
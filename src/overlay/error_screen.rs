@@ -0,0 +1,107 @@
+use rlua::Error;
+
+use crate::{
+    c_singleton,
+    gamestate::stop_game,
+    get_s_val,
+    luautils::{error_line, is_exit, print_err},
+    set_s_val,
+    system::Keycode,
+    Singleton,
+};
+
+use super::{
+    canvas_functions::*,
+    editor::goto_line,
+    overlay::set_overlay,
+    terminal::add_line_to_stdout,
+    OverlayType,
+};
+
+static LINE_LENGTH: usize = 49;
+static VISIBLE_LINES: usize = 26;
+
+c_singleton!(LINES, Vec<String>, Vec::new);
+c_singleton!(ERROR_LINE, Option<usize>, || None);
+
+static mut SCROLL: usize = 0;
+
+// stops the game and shows the error. errors caused by `stop()` just return to the terminal
+pub fn show_error(err: Error) {
+    let exit = is_exit(&err);
+    let msg = print_err(err);
+    stop_game();
+    if exit {
+        return;
+    }
+    // keep the message around in the terminal after the error screen is closed
+    add_line_to_stdout(msg.lines().next().unwrap_or("").to_lowercase());
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in msg.replace('\t', "  ").replace('\r', "").split('\n') {
+        let chars: Vec<char> = line.to_lowercase().chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        for chunk in chars.chunks(LINE_LENGTH) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+
+    set_s_val!(ERROR_LINE, error_line(&msg));
+    set_s_val!(LINES, lines);
+    unsafe {
+        SCROLL = 0;
+    }
+    set_overlay(OverlayType::Error);
+}
+
+pub fn init() {}
+
+pub fn update() {}
+
+pub fn render() {
+    clear(Some(0));
+    rectfill(0, 0, 200, 7, 2);
+    print("runtime error", Some(1), Some(1), Some(12));
+
+    let lines = get_s_val!(LINES);
+    let scroll = unsafe { SCROLL };
+    for (i, line) in lines.iter().enumerate().skip(scroll).take(VISIBLE_LINES) {
+        print(
+            line,
+            Some(1),
+            Some(9 + (i - scroll) as i32 * 6),
+            Some(if i == 0 { 12 } else { 13 }),
+        );
+    }
+
+    rectfill(0, 173, 200, 7, 2);
+    let hint = if let Some(line) = get_s_val!(ERROR_LINE) {
+        format!("enter: go to line {}  esc: terminal", line)
+    } else {
+        "esc: terminal".to_string()
+    };
+    print(&hint, Some(1), Some(174), None);
+}
+
+pub fn handle_key(key: Keycode) {
+    match key {
+        Keycode::Escape => set_overlay(OverlayType::None),
+        Keycode::Return => {
+            if let Some(line) = *get_s_val!(ERROR_LINE) {
+                set_overlay(OverlayType::CodeEditor);
+                goto_line(line.max(1) - 1);
+            }
+        }
+        Keycode::Up => unsafe {
+            SCROLL = SCROLL.saturating_sub(1);
+        },
+        Keycode::Down => unsafe {
+            if SCROLL + VISIBLE_LINES < LINES.get().len() {
+                SCROLL += 1;
+            }
+        },
+        _ => {}
+    }
+}
@@ -63,7 +63,7 @@ pub fn update() {
             }
         } else if item.ends_with("/") {
             unsafe {
-                get_s_val!(PATH).push(&item[0..item.len() - 1]);
+                PATH.get().push(&item[0..item.len() - 1]);
                 ITEMS.reset();
                 VALUE.reset();
             }
@@ -0,0 +1,223 @@
+use crate::{
+    charmap::{code_to_char, put_char_on_canvas_custom},
+    font::{Font, Glyph, GLYPH_SIZE},
+    gamestate::{get_font, get_font_mut},
+    get_s_val,
+    keyboard::mouse_button_down,
+    sprites::{IMG_ARR_LEFT, IMG_ARR_RIGHT},
+    system::{Keycode, MouseButton},
+};
+
+use super::{canvas_functions::*, spr::pad_start};
+
+// the charset shows 128 characters, starting at the space
+static FIRST_CODE: u32 = 32;
+static CHARSET_SIZE: u32 = 128;
+static MAX_LINE_HEIGHT: u8 = 16;
+
+static mut CURCHAR: u32 = 65;
+
+fn current_char() -> Option<char> {
+    code_to_char(unsafe { CURCHAR })
+}
+
+fn current_glyph() -> Option<&'static Glyph> {
+    get_font()
+        .as_ref()
+        .and_then(|font| font.glyphs.get(unsafe { CURCHAR } as usize))
+        .filter(|glyph| glyph.width > 0)
+}
+
+// the glyph of the current char in the cart's font. starts out as a copy of the builtin glyph
+fn current_glyph_mut() -> &'static mut Glyph {
+    let builtin = current_char().map_or(Glyph::default(), Glyph::from_builtin);
+    let glyph = &mut get_font_mut().glyphs[unsafe { CURCHAR } as usize];
+    if glyph.width < 1 {
+        *glyph = builtin;
+        glyph.width = glyph.width.max(1);
+    }
+    glyph
+}
+
+fn put_char(font: &Option<Font>, char: char, x: i32, y: i32) -> i32 {
+    font.as_ref()
+        .and_then(|font| font.put_char(char, x, y, 12, set_pixel))
+        .unwrap_or_else(|| put_char_on_canvas_custom(char, x, y, 13, set_pixel)) as i32
+}
+
+pub fn render() {
+    clear(Some(0));
+    let font = get_font();
+    let glyph = current_glyph();
+
+    // width and line height
+    print("width", Some(9), Some(36), None);
+    get_s_val!(IMG_ARR_LEFT).put_on_canvas(set_pixel, 15, 44);
+    get_s_val!(IMG_ARR_RIGHT).put_on_canvas(set_pixel, 37, 44);
+    rectfill(22, 43, 13, 7, 15);
+    print(
+        &glyph.map_or("---".to_string(), |g| pad_start(g.width.to_string(), '0', 3)),
+        Some(23),
+        Some(44),
+        None,
+    );
+
+    print("line", Some(9), Some(56), None);
+    get_s_val!(IMG_ARR_LEFT).put_on_canvas(set_pixel, 15, 64);
+    get_s_val!(IMG_ARR_RIGHT).put_on_canvas(set_pixel, 37, 64);
+    rectfill(22, 63, 13, 7, 15);
+    let line_height = font.as_ref().map_or(6, |f| f.line_height);
+    print(
+        &pad_start(line_height.to_string(), '0', 3),
+        Some(23),
+        Some(64),
+        None,
+    );
+
+    // canvas. pixels right of the glyph's width are darker
+    rect(70, 21, 84, 84, 12);
+    rect(71, 22, 82, 82, 0);
+    let builtin = current_char().map_or(Glyph::default(), Glyph::from_builtin);
+    let shown = glyph.unwrap_or(&builtin);
+    for y in 0..GLYPH_SIZE {
+        for x in 0..GLYPH_SIZE {
+            let color = if shown.get(x, y) {
+                if glyph.is_some() {
+                    12
+                } else {
+                    13
+                }
+            } else if x < shown.width as usize {
+                0
+            } else {
+                1
+            };
+            rectfill(72 + x as i32 * 10, 23 + y as i32 * 10, 10, 10, color);
+        }
+    }
+
+    // charset
+    print("charset", Some(1), Some(102), None);
+    rectfill(0, 108, 129, 1, 15);
+    rectfill(0, 108, 1, 65, 15);
+    rectfill(129, 108, 1, 65, 15);
+    for i in 0..CHARSET_SIZE {
+        if let Some(char) = code_to_char(FIRST_CODE + i) {
+            put_char(
+                font,
+                char,
+                1 + (i % 16) as i32 * 8,
+                109 + (i / 16) as i32 * 8,
+            );
+        }
+    }
+    let sel = unsafe { CURCHAR } - FIRST_CODE;
+    rect(
+        (sel % 16) as i32 * 8,
+        108 + (sel / 16) as i32 * 8,
+        10,
+        10,
+        12,
+    );
+
+    // preview
+    for (i, line) in ["ABC abc", "123 !?"].iter().enumerate() {
+        let mut x = 134;
+        let y = 112 + i as i32 * line_height as i32;
+        for char in line.chars() {
+            x += put_char(font, char, x, y);
+        }
+    }
+
+    // bottom bar
+    rectfill(0, 173, 200, 7, 2);
+    print(
+        &format!(
+            "code:{} width:{} line:{}",
+            unsafe { CURCHAR },
+            glyph.map_or(0, |g| g.width),
+            line_height
+        ),
+        Some(1),
+        Some(174),
+        None,
+    );
+}
+
+fn change_selected(value: i32) {
+    let sel = (unsafe { CURCHAR } - FIRST_CODE) as i32 + value;
+    unsafe {
+        CURCHAR = FIRST_CODE + sel.rem_euclid(CHARSET_SIZE as i32) as u32;
+    }
+}
+
+pub fn keydown(key: Keycode) {
+    match key {
+        Keycode::Left => change_selected(-1),
+        Keycode::Right => change_selected(1),
+        Keycode::Up => change_selected(-16),
+        Keycode::Down => change_selected(16),
+        // go back to the builtin glyph
+        Keycode::Delete if current_glyph().is_some() => {
+            get_font_mut().glyphs[unsafe { CURCHAR } as usize] = Glyph::default();
+        }
+        _ => {}
+    }
+}
+
+pub fn mousedown(button: MouseButton, x: u32, y: u32) {
+    if current_char().is_some() && (72..152).contains(&x) && (23..103).contains(&y) {
+        let (gx, gy) = ((x - 72) as usize / 10, (y - 23) as usize / 10);
+        let glyph = current_glyph_mut();
+        match button {
+            MouseButton::Left => {
+                glyph.set(gx, gy, true);
+                glyph.width = glyph.width.max(gx as u8 + 1);
+            }
+            MouseButton::Right => glyph.set(gx, gy, false),
+            _ => {}
+        }
+    }
+
+    if button != MouseButton::Left {
+        return;
+    }
+
+    // width
+    if (44..=49).contains(&y) && current_char().is_some() {
+        if (15..=19).contains(&x) {
+            let glyph = current_glyph_mut();
+            glyph.width = (glyph.width - 1).max(1);
+        } else if (37..=42).contains(&x) {
+            let glyph = current_glyph_mut();
+            glyph.width = (glyph.width + 1).min(GLYPH_SIZE as u8);
+        }
+    }
+
+    // line height
+    if (64..=69).contains(&y) {
+        let font = get_font_mut();
+        if (15..=19).contains(&x) {
+            font.line_height = (font.line_height - 1).max(1);
+        } else if (37..=42).contains(&x) {
+            font.line_height = (font.line_height + 1).min(MAX_LINE_HEIGHT);
+        }
+    }
+
+    // charset
+    if (109..173).contains(&y) && (1..129).contains(&x) {
+        unsafe {
+            CURCHAR = FIRST_CODE + (y - 109) / 8 * 16 + (x - 1) / 8;
+        }
+    }
+}
+
+pub fn mousemove(x: u32, y: u32) {
+    if (72..152).contains(&x) && (23..103).contains(&y) {
+        if mouse_button_down(MouseButton::Left) {
+            mousedown(MouseButton::Left, x, y);
+        } else if mouse_button_down(MouseButton::Right) {
+            mousedown(MouseButton::Right, x, y);
+        }
+    }
+}
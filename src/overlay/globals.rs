@@ -2,7 +2,7 @@
 
 use std::fmt::Debug;
 
-use crate::{pub_c_singleton, Singleton, HEIGHT, WIDTH};
+use crate::{canvas_functions::Color, pub_c_singleton, Singleton, HEIGHT, WIDTH};
 
 use super::terminal::init;
 
@@ -12,6 +12,8 @@ pub enum OverlayType {
     Options,
     CodeEditor,
     Explore,
+    Error,
+    MemoryInspector,
     None,
 }
 
@@ -20,10 +22,7 @@ pub_c_singleton!(OVERLAY, OverlayType, || {
     OverlayType::None
 });
 
-pub_c_singleton!(DISPLAYMEM, Vec<u8>, || {
-    let mut vec = <Vec<u8>>::new();
-    for _ in 0..WIDTH * HEIGHT {
-        vec.push(0);
-    }
-    vec
-});
+// the rgb colors of rgbrectfill for this frame, pixels from 16 on use them instead of the overlay palette
+pub_c_singleton!(SWATCHES, Vec<Color>, Vec::new);
+
+pub_c_singleton!(DISPLAYMEM, Vec<u8>, || vec![0; (WIDTH * HEIGHT) as usize]);
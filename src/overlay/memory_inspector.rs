@@ -0,0 +1,234 @@
+use crate::{
+    c_singleton,
+    get_s_val,
+    memory::{getmem, memory_sections, peek, poke, MemorySection},
+    system::{Keycode, MouseButton},
+    utils::is_shift_pressed,
+    Singleton,
+};
+
+use super::{
+    canvas_functions::*, key_utils::keycode_to_character, overlay::set_overlay, OverlayType,
+};
+
+static ROW_BYTES: usize = 8;
+static VISIBLE_ROWS: usize = 18;
+static HEX_Y: i32 = 63;
+static SECTIONS_Y: i32 = 16;
+static MAX_PINS: usize = 7;
+
+static mut CURSOR: usize = 0;
+static mut SCROLL: usize = 0;
+// the next typed hex digit goes into the high nibble of the byte
+static mut HIGH_NIBBLE: bool = true;
+
+c_singleton!(PINS, Vec<usize>, Vec::new);
+
+fn short_name(section: &MemorySection) -> String {
+    section.name().trim_end_matches(" Memory").to_lowercase()
+}
+
+fn printable(byte: u8) -> char {
+    if (32..127).contains(&byte) {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+// moves the cursor and scrolls so it stays visible
+fn move_cursor(to: usize) {
+    let len = getmem().len();
+    if len < 1 {
+        return;
+    }
+    unsafe {
+        CURSOR = to.min(len - 1);
+        HIGH_NIBBLE = true;
+        let row = CURSOR / ROW_BYTES;
+        if row < SCROLL {
+            SCROLL = row;
+        } else if row >= SCROLL + VISIBLE_ROWS {
+            SCROLL = row + 1 - VISIBLE_ROWS;
+        }
+    }
+}
+
+fn toggle_pin(address: usize) {
+    let pins = get_s_val!(PINS);
+    if let Some(idx) = pins.iter().position(|pin| *pin == address) {
+        pins.remove(idx);
+    } else if pins.len() < MAX_PINS {
+        pins.push(address);
+    }
+}
+
+pub fn init() {
+    move_cursor(unsafe { CURSOR });
+}
+
+pub fn update() {}
+
+pub fn render() {
+    clear(Some(0));
+    rectfill(0, 0, 200, 7, 2);
+    print("memory inspector", Some(1), Some(1), Some(12));
+
+    let cursor = unsafe { CURSOR };
+    let pins = get_s_val!(PINS);
+
+    // sections
+    print("section  start    len", Some(1), Some(9), Some(14));
+    for (i, section) in memory_sections().iter().enumerate() {
+        let start = section.start() as usize;
+        let contains = cursor >= start && cursor < start + section.len() as usize;
+        print(
+            &format!("{:<9}{:05x} {:>6}", short_name(section), start, section.len()),
+            Some(1),
+            Some(SECTIONS_Y + i as i32 * 6),
+            Some(if contains { 12 } else { 13 }),
+        );
+    }
+
+    // pinned addresses
+    print("pinned", Some(132), Some(9), Some(14));
+    for (i, pin) in pins.iter().enumerate() {
+        print(
+            &format!("{:05x}: {:02x}", pin, peek(*pin)),
+            Some(132),
+            Some(SECTIONS_Y + i as i32 * 6),
+            Some(10),
+        );
+    }
+    rectfill(0, HEX_Y - 3, 200, 1, 2);
+
+    // hex and ascii view
+    let mem = getmem();
+    let scroll = unsafe { SCROLL };
+    for row in 0..VISIBLE_ROWS {
+        let addr = (scroll + row) * ROW_BYTES;
+        if addr >= mem.len() {
+            break;
+        }
+        let y = HEX_Y + row as i32 * 6;
+        print(&format!("{:05x}", addr), Some(1), Some(y), Some(14));
+        for i in 0..ROW_BYTES.min(mem.len() - addr) {
+            let byte = mem[addr + i];
+            let x = 24 + i as i32 * 12;
+            let color = if addr + i == cursor {
+                rectfill(x - 1, y - 1, 9, 7, 2);
+                rectfill(123 + i as i32 * 4, y - 1, 5, 7, 2);
+                12
+            } else if pins.contains(&(addr + i)) {
+                10
+            } else {
+                13
+            };
+            print(&format!("{:02x}", byte), Some(x), Some(y), Some(color));
+            print(&printable(byte).to_string(), Some(124 + i as i32 * 4), Some(y), Some(color));
+        }
+    }
+
+    // bottom bar
+    rectfill(0, 173, 200, 7, 2);
+    print(
+        &format!(
+            "{:05x}={:3}  enter:pin tab:section",
+            cursor,
+            mem.get(cursor).copied().unwrap_or(0)
+        ),
+        Some(1),
+        Some(174),
+        None,
+    );
+}
+
+// the pinned addresses, shown in the corner while the game runs
+pub fn render_pins() {
+    let pins = get_s_val!(PINS);
+    for (i, pin) in pins.iter().enumerate() {
+        let y = 1 + i as i32 * 7;
+        rectfill(155, y - 1, 45, 7, 2);
+        print(&format!("{:05x}:{:02x}", pin, peek(*pin)), Some(156), Some(y), Some(12));
+    }
+}
+
+pub fn handle_key(key: Keycode) {
+    let cursor = unsafe { CURSOR };
+    let page = if is_shift_pressed() { VISIBLE_ROWS } else { 1 };
+    match key {
+        Keycode::Left => move_cursor(cursor.saturating_sub(1)),
+        Keycode::Right => move_cursor(cursor + 1),
+        Keycode::Up => move_cursor(cursor.saturating_sub(ROW_BYTES * page)),
+        Keycode::Down => move_cursor(cursor + ROW_BYTES * page),
+        Keycode::Tab => {
+            let starts: Vec<usize> = memory_sections().iter().map(|s| s.start() as usize).collect();
+            let next = if is_shift_pressed() {
+                starts.iter().rev().find(|start| **start < cursor).copied()
+            } else {
+                starts.iter().find(|start| **start > cursor).copied()
+            };
+            move_cursor(next.unwrap_or(0));
+        }
+        Keycode::Return => toggle_pin(cursor),
+        Keycode::Backspace => set_overlay(OverlayType::PauseMenu),
+        _ => {
+            // typing hex digits edits the byte under the cursor
+            if let Some(digit) = keycode_to_character(Some(key)).and_then(|c| c.to_digit(16)) {
+                let byte = peek(cursor);
+                unsafe {
+                    if HIGH_NIBBLE {
+                        poke(cursor, (byte & 0x0f) | (digit as u8) << 4);
+                        HIGH_NIBBLE = false;
+                    } else {
+                        poke(cursor, (byte & 0xf0) | digit as u8);
+                        move_cursor(cursor + 1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn handle_scroll(dy: i32) {
+    let rows = getmem().len().div_ceil(ROW_BYTES);
+    unsafe {
+        SCROLL = (SCROLL as i32 - dy).clamp(0, rows.saturating_sub(VISIBLE_ROWS) as i32) as usize;
+    }
+}
+
+pub fn handle_mousedown(button: MouseButton, x: u32, y: u32) {
+    let (x, y) = (x as i32, y as i32);
+    // sections
+    if y >= SECTIONS_Y && y < HEX_Y - 3 && x < 130 {
+        if let Some(section) = memory_sections().get(((y - SECTIONS_Y) / 6) as usize) {
+            move_cursor(section.start() as usize);
+        }
+    }
+    // pinned addresses, right click unpins them
+    if y >= SECTIONS_Y && y < HEX_Y - 3 && x >= 132 {
+        if let Some(pin) = get_s_val!(PINS).get(((y - SECTIONS_Y) / 6) as usize).copied() {
+            match button {
+                MouseButton::Right => toggle_pin(pin),
+                _ => move_cursor(pin),
+            }
+        }
+    }
+    // hex and ascii view
+    if y >= HEX_Y && y < HEX_Y + VISIBLE_ROWS as i32 * 6 {
+        let col = if x >= 23 && x < 23 + ROW_BYTES as i32 * 12 {
+            Some((x - 23) / 12)
+        } else if x >= 124 && x < 124 + ROW_BYTES as i32 * 4 {
+            Some((x - 124) / 4)
+        } else {
+            None
+        };
+        if let Some(col) = col {
+            let row = unsafe { SCROLL } + ((y - HEX_Y) / 6) as usize;
+            move_cursor(row * ROW_BYTES + col as usize);
+            if button == MouseButton::Right {
+                toggle_pin(unsafe { CURSOR });
+            }
+        }
+    }
+}
@@ -16,8 +16,8 @@ pub fn reset() {
     }
 }
 
-pub fn update(items: &Vec<String>) -> Option<u32> {
-    if items.len() < 1 {
+pub fn update(items: &[String]) -> Option<u32> {
+    if items.is_empty() {
         return None;
     }
 
@@ -65,7 +65,7 @@ pub fn get_selected() -> u32 {
     unsafe { selected }
 }
 
-pub fn render(items: &Vec<String>) {
+pub fn render(items: &[String]) {
     let selected_item = unsafe { selected };
     let dsty = selected_item as i32 * 8 - 1;
     if dsty != unsafe { y.floor() as i32 } {
@@ -76,11 +76,11 @@ pub fn render(items: &Vec<String>) {
         }
     }
     rectfill(0, 86, 200, 7, 2);
-    for i in 0..items.len() {
+    for (i, item) in items.iter().enumerate() {
         cursor(
-            Some((200 - items[i].len() as i32 * 4) / 2),
+            Some((200 - item.len() as i32 * 4) / 2),
             Some(i as i32 * 8 + 86 - unsafe { y.floor() as i32 }),
         );
-        print(&items[i].to_lowercase(), None, None, None);
+        print(&item.to_lowercase(), None, None, None);
     }
 }
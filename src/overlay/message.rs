@@ -36,9 +36,9 @@ pub fn reset_statusbar() {
 pub fn render() {    
     unsafe {
         if now() - LAST_UPDATE > 100 {
-            if MESSAGE.len() > 0 && !SHOULD_CLOSE && OPEN < 7 {
+            if !MESSAGE.is_empty() && !SHOULD_CLOSE && OPEN < 7 {
                 OPEN += 1
-            } else if (MESSAGE.len() < 1 || SHOULD_CLOSE) && OPEN > 0 {
+            } else if (MESSAGE.is_empty() || SHOULD_CLOSE) && OPEN > 0 {
                 OPEN -= 1;
             }
         }
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod overlay;
 mod canvas_functions;
 mod globals;
@@ -12,10 +13,16 @@ mod key_utils;
 pub mod message;
 mod editor;
 mod syntax_parser;
+mod completion;
 mod editor_manager;
 mod spr;
 mod sized_vec;
 mod sfx;
 mod mouse_cursor;
 mod explore;
+mod font;
+mod palette;
+mod error_screen;
+mod memory_inspector;
+pub use error_screen::show_error;
 pub use syntax_parser::vec_to_regex;
\ No newline at end of file
@@ -9,7 +9,7 @@ c_singleton!(CURSOR_HIGHLIGHT, Image, ||parse_image(7, 7, "000f00000fcf000f000f0
 
 pub fn render() {
     let (mut x, mut y) = unsafe {(M_X as i32, M_Y as i32)};
-    if get_s_val!(OVERLAY) == &OverlayType::CodeEditor && is_overlay_active() && get_s_val!(CURRENT_EDITOR) == &Editor::Sprites && x >= 71 && x < 153 && y >= 22 && y < 104 {
+    if get_s_val!(OVERLAY) == &OverlayType::CodeEditor && is_overlay_active() && get_s_val!(CURRENT_EDITOR) == &Editor::Sprites && (71..153).contains(&x) && (22..104).contains(&y) {
         x -= 2;
         y -= 3;
         get_s_val!(CURSOR_HIGHLIGHT).put_on_canvas(custom_set_pixel, x, y);
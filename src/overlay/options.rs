@@ -13,14 +13,12 @@ use crate::{
 };
 
 c_singleton!(ITEMS, Vec<String>, || {
-    let mut vec: Vec<String> = Vec::new();
-
-    vec.push("palette".to_string());
-    vec.push("volume".to_string());
-    vec.push("".to_string());
-    vec.push("back".to_string());
-
-    vec
+    vec![
+        "palette".to_string(),
+        "volume".to_string(),
+        "".to_string(),
+        "back".to_string(),
+    ]
 });
 
 pub fn render() {
@@ -1,5 +1,5 @@
 use crate::audio::{is_muted, set_muted};
-use crate::canvas_functions::PALETTE1;
+use crate::canvas_functions::{Color, PALETTE1};
 use crate::gamestate::game_is_running;
 use crate::get_s_val;
 use crate::memory::keymemory;
@@ -12,8 +12,17 @@ use super::editor_manager::{
     handle_mousemove as handle_mousemove_editor, handle_scroll as handle_scroll_editor,
     init as init_editor, render as render_editor, update as update_editor,
 };
+use super::error_screen::{
+    handle_key as handle_key_error, init as init_error, render as render_error,
+    update as update_error,
+};
 use super::explore::{init as init_explore, render as render_explore, update as update_explore};
-use super::globals::{OverlayType, DISPLAYMEM, OVERLAY};
+use super::globals::{OverlayType, DISPLAYMEM, OVERLAY, SWATCHES};
+use super::memory_inspector::{
+    handle_key as handle_key_memory, handle_mousedown as handle_mousedown_memory,
+    handle_scroll as handle_scroll_memory, init as init_memory, render as render_memory,
+    render_pins, update as update_memory,
+};
 use super::message::{render as rendermessage, set_message};
 use super::mouse_cursor::{mousemove as mousemove_cursor, render as render_cursor};
 use super::options::{cycle_palette, init as initopt, render as renderopt, update as updateopt};
@@ -39,12 +48,15 @@ pub fn set_overlay(new: OverlayType) {
         OverlayType::None => initterm(),
         OverlayType::CodeEditor => init_editor(),
         OverlayType::Explore => init_explore(),
+        OverlayType::Error => init_error(),
+        OverlayType::MemoryInspector => init_memory(),
     }
     unsafe { OVERLAY.set(new) };
 }
 
 pub fn renderoverlay() {
     clear(Some(0));
+    get_s_val!(SWATCHES).clear();
     if is_overlay_active() {
         cursor(None, None);
         let overlaytype = unsafe { OVERLAY.get() };
@@ -54,7 +66,11 @@ pub fn renderoverlay() {
             OverlayType::None => renderterm(),
             OverlayType::CodeEditor => render_editor(),
             OverlayType::Explore => render_explore(),
+            OverlayType::Error => render_error(),
+            OverlayType::MemoryInspector => render_memory(),
         }
+    } else {
+        render_pins();
     }
     if is_overlay_active() || get_s_val!(keymemory).get_at_addr_d(0x3b) > 0 {
         render_cursor();
@@ -73,6 +89,15 @@ pub fn updateoverlay() {
         OverlayType::None => updateterm(),
         OverlayType::CodeEditor => update_editor(),
         OverlayType::Explore => update_explore(),
+        OverlayType::Error => update_error(),
+        OverlayType::MemoryInspector => update_memory(),
+    }
+}
+
+fn overlay_color(index: u8) -> Color {
+    match index {
+        0..=15 => PALETTE1[index as usize],
+        _ => get_s_val!(SWATCHES).get(index as usize - 16).copied().unwrap_or(PALETTE1[0]),
     }
 }
 
@@ -80,13 +105,13 @@ pub fn ov_write_to_sdl(mem: &mut Vec<u8>) {
     let imagemem = unsafe { DISPLAYMEM.get() };
     if mem.len() != imagemem.len() * 4 || is_overlay_active() {
         mem.clear();
-        for i in 0..imagemem.len() {
-            PALETTE1[imagemem[i] as usize].sdl_write_to_vec(mem);
+        for index in imagemem.iter() {
+            overlay_color(*index).sdl_write_to_vec(mem);
         }
     } else {
-        for i in 0..imagemem.len() {
-            if imagemem[i] != 0 {
-                let (r, g, b) = PALETTE1[imagemem[i] as usize].get_values();
+        for (i, index) in imagemem.iter().enumerate() {
+            if *index != 0 {
+                let (r, g, b) = overlay_color(*index).get_values();
                 let off = i * 4;
                 mem[off] = b;
                 mem[off + 1] = g;
@@ -98,7 +123,7 @@ pub fn ov_write_to_sdl(mem: &mut Vec<u8>) {
 }
 
 pub fn ov_handle_keydown(key: Keycode) {
-    if !match key {
+    let handled = match key {
         Keycode::Escape => {
             if !game_is_running() {
                 false
@@ -111,52 +136,35 @@ pub fn ov_handle_keydown(key: Keycode) {
                 true
             }
         }
-        Keycode::P => {
-            if is_ctrl_pressed() {
-                cycle_palette(true);
-                set_message("cycled palette (ctrl+p)");
-                true
-            } else {
-                false
-            }
+        Keycode::P if is_ctrl_pressed() => {
+            cycle_palette(true);
+            set_message("cycled palette (ctrl+p)");
+            true
         }
-        Keycode::M => {
-            if game_is_running() && is_ctrl_pressed() {
-                set_muted(None);
-                if is_muted() {
-                    set_message("muted (ctrl+m)");
-                } else {
-                    set_message("unmuted (ctrl+m)");
-                }
-                true
+        Keycode::M if game_is_running() && is_ctrl_pressed() => {
+            set_muted(None);
+            if is_muted() {
+                set_message("muted (ctrl+m)");
             } else {
-                false
+                set_message("unmuted (ctrl+m)");
             }
+            true
         }
-
         _ => false,
-    } {
-        if is_overlay_active() {
-            match get_s_val!(OVERLAY) {
-                OverlayType::None => term_handle_key(key),
-                OverlayType::CodeEditor => handle_key_editor(key),
-                _ => {}
-            };
-        }
+    };
+    if !handled && is_overlay_active() {
+        match get_s_val!(OVERLAY) {
+            OverlayType::None => term_handle_key(key),
+            OverlayType::CodeEditor => handle_key_editor(key),
+            OverlayType::Error => handle_key_error(key),
+            OverlayType::MemoryInspector => handle_key_memory(key),
+            _ => {}
+        };
     }
 }
 
-pub fn ov_handle_keyup(key: Keycode) {
-    if !match key {
-        _ => false,
-    } {
-        if is_overlay_active() {
-            match get_s_val!(OVERLAY) {
-                _ => {}
-            }
-        }
-    }
-}
+// no overlay handles key releases yet
+pub fn ov_handle_keyup(_key: Keycode) {}
 
 pub fn ov_handle_scroll(dy: i32) {
     if !is_overlay_active() {
@@ -164,6 +172,7 @@ pub fn ov_handle_scroll(dy: i32) {
     }
     match get_s_val!(OVERLAY) {
         OverlayType::CodeEditor => handle_scroll_editor(dy),
+        OverlayType::MemoryInspector => handle_scroll_memory(dy),
         _ => {}
     }
 }
@@ -174,6 +183,7 @@ pub fn ov_handle_mousedown(button: MouseButton, x: u32, y: u32) {
     }
     match get_s_val!(OVERLAY) {
         OverlayType::CodeEditor => handle_mousedown_editor(button, x, y),
+        OverlayType::MemoryInspector => handle_mousedown_memory(button, x, y),
         _ => {}
     }
 }
@@ -185,8 +195,7 @@ pub fn ov_handle_mousemove(x: u32, y: u32) {
     if !is_overlay_active() {
         return;
     }
-    match get_s_val!(OVERLAY) {
-        OverlayType::CodeEditor => handle_mousemove_editor(x, y),
-        _ => {}
-    };
+    if *get_s_val!(OVERLAY) == OverlayType::CodeEditor {
+        handle_mousemove_editor(x, y);
+    }
 }
@@ -0,0 +1,178 @@
+use crate::{
+    canvas_functions::{get_palette, Color, PALETTES},
+    gamestate::get_palettes_mut,
+    get_s_val,
+    keyboard::mouse_button_down,
+    sprites::{IMG_ARR_LEFT, IMG_ARR_RIGHT},
+    system::{Keycode, MouseButton},
+    utils::is_shift_pressed,
+};
+
+use super::{canvas_functions::*, spr::pad_start};
+
+static GRID_X: i32 = 60;
+static GRID_Y: i32 = 14;
+static SLIDERS_Y: i32 = 110;
+static SLIDER_X: i32 = 24;
+static SLIDER_WIDTH: i32 = 128;
+
+static mut PALETTE: u8 = 0;
+static mut COLOR: u8 = 0;
+// 0: red, 1: green, 2: blue
+static mut CHANNEL: u8 = 0;
+
+fn current_color() -> Color {
+    get_palette(unsafe { PALETTE })[unsafe { COLOR } as usize]
+}
+
+fn set_channel(channel: u8, value: u8) {
+    let (mut r, mut g, mut b) = current_color().get_values();
+    match channel {
+        0 => r = value,
+        1 => g = value,
+        _ => b = value,
+    }
+    get_palettes_mut()[unsafe { PALETTE } as usize][unsafe { COLOR } as usize] = Color::rgb(r, g, b);
+}
+
+fn channel_value(color: Color, channel: u8) -> u8 {
+    let (r, g, b) = color.get_values();
+    match channel {
+        0 => r,
+        1 => g,
+        _ => b,
+    }
+}
+
+fn channel_color(channel: u8, value: u8) -> Color {
+    match channel {
+        0 => Color::rgb(value, 0, 0),
+        1 => Color::rgb(0, value, 0),
+        _ => Color::rgb(0, 0, value),
+    }
+}
+
+pub fn render() {
+    clear(Some(0));
+    let palette = unsafe { PALETTE };
+    let selected = unsafe { COLOR };
+    let colors = get_palette(palette);
+    let color = current_color();
+
+    // palette
+    print("palette", Some(9), Some(16), None);
+    get_s_val!(IMG_ARR_LEFT).put_on_canvas(set_pixel, 15, 24);
+    get_s_val!(IMG_ARR_RIGHT).put_on_canvas(set_pixel, 37, 24);
+    rectfill(22, 23, 13, 7, 15);
+    print(
+        &pad_start((palette + 1).to_string(), '0', 3),
+        Some(23),
+        Some(24),
+        None,
+    );
+
+    // colors
+    for i in 0..16 {
+        let x = GRID_X + (i % 4) * 22;
+        let y = GRID_Y + (i / 4) * 22;
+        rect(x - 1, y - 1, 22, 22, if i as u8 == selected { 12 } else { 1 });
+        rgbrectfill(x, y, 20, 20, colors[i as usize]);
+    }
+
+    // the selected color
+    rect(159, 13, 32, 32, 12);
+    rgbrectfill(160, 14, 30, 30, color);
+    print(&format!("#{:06x}", color.to_hex()), Some(160), Some(48), None);
+
+    // sliders
+    for channel in 0..3u8 {
+        let y = SLIDERS_Y + channel as i32 * 12;
+        let value = channel_value(color, channel);
+        let label_color = if channel == unsafe { CHANNEL } { 12 } else { 13 };
+        print(["r", "g", "b"][channel as usize], Some(9), Some(y), Some(label_color));
+        rect(SLIDER_X - 1, y - 1, SLIDER_WIDTH + 2, 7, label_color);
+        rgbrectfill(
+            SLIDER_X,
+            y,
+            value as i32 * SLIDER_WIDTH / 255,
+            5,
+            channel_color(channel, value),
+        );
+        print(&pad_start(value.to_string(), '0', 3), Some(158), Some(y), None);
+    }
+
+    // bottom bar
+    rectfill(0, 173, 200, 7, 2);
+    print(
+        &format!("palette {} color {}  del: reset", palette + 1, selected),
+        Some(1),
+        Some(174),
+        None,
+    );
+}
+
+fn change_palette(right: bool) {
+    unsafe {
+        PALETTE = if right { (PALETTE + 1) % 4 } else { (PALETTE + 3) % 4 };
+    }
+}
+
+pub fn keydown(key: Keycode) {
+    let channel = unsafe { CHANNEL };
+    let value = channel_value(current_color(), channel);
+    let step = if is_shift_pressed() { 16 } else { 1 };
+    match key {
+        Keycode::Left => set_channel(channel, value.saturating_sub(step)),
+        Keycode::Right => set_channel(channel, value.saturating_add(step)),
+        Keycode::Up => unsafe { CHANNEL = (CHANNEL + 2) % 3 },
+        Keycode::Down => unsafe { CHANNEL = (CHANNEL + 1) % 3 },
+        // go back to the builtin color
+        Keycode::Delete => unsafe {
+            get_palettes_mut()[PALETTE as usize][COLOR as usize] =
+                PALETTES[PALETTE as usize][COLOR as usize];
+        },
+        _ => {}
+    }
+}
+
+pub fn mousedown(button: MouseButton, x: u32, y: u32) {
+    if button != MouseButton::Left {
+        return;
+    }
+    let (x, y) = (x as i32, y as i32);
+
+    // palette
+    if (24..=29).contains(&y) {
+        if (15..=19).contains(&x) {
+            change_palette(false);
+        } else if (37..=42).contains(&x) {
+            change_palette(true);
+        }
+    }
+
+    // colors
+    if x >= GRID_X && x < GRID_X + 88 && y >= GRID_Y && y < GRID_Y + 88 {
+        unsafe {
+            COLOR = ((y - GRID_Y) / 22 * 4 + (x - GRID_X) / 22) as u8;
+        }
+    }
+
+    // sliders
+    if x >= SLIDER_X - 1 && x <= SLIDER_X + SLIDER_WIDTH && y >= SLIDERS_Y - 1 && y < SLIDERS_Y + 35 {
+        let channel = ((y - SLIDERS_Y + 1) / 12) as u8;
+        let value = ((x - SLIDER_X).clamp(0, SLIDER_WIDTH) * 255 / SLIDER_WIDTH) as u8;
+        unsafe {
+            CHANNEL = channel;
+        }
+        set_channel(channel, value);
+    }
+}
+
+pub fn mousemove(x: u32, y: u32) {
+    if mouse_button_down(MouseButton::Left)
+        && y as i32 >= SLIDERS_Y - 1
+        && (y as i32) < SLIDERS_Y + 35
+    {
+        mousedown(MouseButton::Left, x, y);
+    }
+}
@@ -1,29 +1,28 @@
 use super::{
     background::{render_bg, update_bg},
+    error_screen::show_error,
     menu,
-    overlay::{hide_overlay, set_overlay}, terminal::add_line_to_stdout,
+    overlay::{hide_overlay, set_overlay},
 };
 use crate::{
     c_singleton,
     gamestate::{stop_game, run_game},
     get_s_val,
-    luautils::print_err,
     Singleton,
 };
 use std::process::exit;
 
 c_singleton!(ITEMS, Vec<String>, || {
-    let mut v = <Vec<String>>::new();
-
-    v.push("Continue game".to_string());
-    v.push("Reset Game".to_string());
-    v.push("Stop Game".to_string());
-    v.push("".to_string());
-    v.push("Options".to_string());
-    v.push("".to_string());
-    v.push("Quit Rainbow16".to_string());
-
-    v
+    vec![
+        "Continue game".to_string(),
+        "Reset Game".to_string(),
+        "Stop Game".to_string(),
+        "".to_string(),
+        "Options".to_string(),
+        "Memory Inspector".to_string(),
+        "".to_string(),
+        "Quit Rainbow16".to_string(),
+    ]
 });
 
 pub fn render() {
@@ -42,18 +41,18 @@ pub fn update() {
             0 => hide_overlay(),
             1 => {
                 stop_game();
+                hide_overlay();
                 if let Some(err) = run_game() {
-                    add_line_to_stdout(print_err(err));
-                    stop_game();
+                    show_error(err);
                 }
-                hide_overlay();
             }
             2 => {
                 stop_game();
                 set_overlay(super::OverlayType::None);
             }
             4 => set_overlay(super::OverlayType::Options),
-            6 => exit(0),
+            5 => set_overlay(super::OverlayType::MemoryInspector),
+            7 => exit(0),
             _ => {}
         }
     }
@@ -36,35 +36,35 @@ static mut CURRENT_WAVE: WaveType = WaveType::SquareWave;
 pub fn render() {
     let audio = get_audio(unsafe { SELECTED as usize });
     // sfx selector
-    print(&"sfx:".to_string(), Some(5), Some(10), None);
+    print("sfx:", Some(5), Some(10), None);
     get_s_val!(IMG_ARR_LEFT).put_on_canvas(set_pixel, 21, 10);
     get_s_val!(IMG_ARR_RIGHT).put_on_canvas(set_pixel, 37, 10);
     rectfill(27, 9, 9, 7, 15);
     print(
-        &format!("{}", unsafe { pad_start(SELECTED.to_string(), '0', 2) }),
+        &unsafe { pad_start(SELECTED.to_string(), '0', 2) },
         Some(28),
         Some(10),
         None,
     );
 
     // speed selector
-    print(&"spd:".to_string(), Some(90), Some(10), None);
+    print("spd:", Some(90), Some(10), None);
     get_s_val!(IMG_ARR_LEFT).put_on_canvas(set_pixel, 106, 10);
     get_s_val!(IMG_ARR_RIGHT).put_on_canvas(set_pixel, 126, 10);
     rectfill(112, 9, 13, 7, 15);
     print(
-        &format!("{}", pad_start(audio.speed.to_string(), '0', 3)),
+        &pad_start(audio.speed.to_string(), '0', 3),
         Some(113),
         Some(10),
         None,
     );
 
     // sound selector
-    print(&"sound".to_string(), Some(5), Some(19), None);
+    print("sound", Some(5), Some(19), None);
     rectfill(4, 25, 192, 120, 15);
 
     // volume selector
-    print(&"volume".to_string(), Some(5), Some(146), None);
+    print("volume", Some(5), Some(146), None);
     rectfill(4, 152, 192, 16, 15);
 
     // render items
@@ -100,7 +100,7 @@ pub fn render() {
 
     // render buttons
     let typ = unsafe { CURRENT_WAVE };
-    let waves = vec![
+    let waves = [
         if typ == WaveType::SquareWave {
             get_s_val!(SQUARE_WAVE_SELECTED)
         } else {
@@ -138,28 +138,26 @@ pub fn render() {
         },
     ];
 
-    for i in 0..waves.len() {
-        waves[i].put_on_canvas(set_pixel, 115 + (i as i32 * 12), 18);
+    for (i, wave) in waves.iter().enumerate() {
+        wave.put_on_canvas(set_pixel, 115 + (i as i32 * 12), 18);
     }
 }
 
 pub fn mousedown(button: MouseButton, x: u32, y: u32) {
     let a = get_audio(unsafe { SELECTED as usize });
     if button == MouseButton::Left {
-        if x >= 21 && x <= 26 && y >= 10 && y <= 15 && unsafe { SELECTED > 0 } {
+        if (21..=26).contains(&x) && (10..=15).contains(&y) && unsafe { SELECTED > 0 } {
             unsafe {
                 SELECTED -= 1;
             }
         }
-        if x >= 37 && x <= 42 && y >= 10 && y <= 15 && unsafe { SELECTED < 31 } {
+        if (37..=42).contains(&x) && (10..=15).contains(&y) && unsafe { SELECTED < 31 } {
             unsafe {
                 SELECTED += 1;
             }
         }
-        if x >= 106
-            && x <= 111
-            && y >= 10
-            && y <= 15
+        if (106..=111).contains(&x)
+            && (10..=15).contains(&y)
             && a.speed > (if is_shift_pressed() { 11 } else { 1 })
         {
             if is_shift_pressed() {
@@ -168,10 +166,8 @@ pub fn mousedown(button: MouseButton, x: u32, y: u32) {
                 a.speed -= 1;
             }
         }
-        if x >= 126
-            && x <= 131
-            && y >= 10
-            && y <= 15
+        if (126..=131).contains(&x)
+            && (10..=15).contains(&y)
             && a.speed < (if is_shift_pressed() { 245 } else { 255 })
         {
             if is_shift_pressed() {
@@ -206,7 +202,7 @@ pub fn mousedown(button: MouseButton, x: u32, y: u32) {
             a.items[index].sound = val;
             a.items[index].wave_type = unsafe { CURRENT_WAVE };
         }
-        if x >= 115 && x <= 195 && y >= 18 && y <= 24 {
+        if (115..=195).contains(&x) && (18..=24).contains(&y) {
             let mut idx = (x - 115) / 12;
             if idx > 6 {
                 idx = 6;
@@ -257,7 +253,7 @@ pub fn keydown(keycode: Keycode) {
                     .as_millis() as u32,
             );
             mem.set_at_addr(102, 1);
-            get_audio(unsafe { SELECTED as usize }).write_to_memory(&mem, 0);
+            get_audio(unsafe { SELECTED as usize }).write_to_memory(mem, 0);
         }
     }
 }
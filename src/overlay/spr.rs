@@ -23,7 +23,7 @@ pub fn pad_start(str: String, char: char, mut length: usize) -> String {
     let mut _s = String::with_capacity(length);
     _s.extend((0..padding_needed).map(|_| char));
     _s.push_str(&str);
-    return _s;
+    _s
 }
 
 pub fn render() {
@@ -53,7 +53,7 @@ pub fn render() {
                 23 + y * 10,
                 10,
                 10,
-                get_s_val!(IMG)[((y + oy as i32) * 128 + x + ox as i32) as usize] as u8,
+                get_s_val!(IMG)[((y + oy as i32) * 128 + x + ox as i32) as usize],
             )
         }
     }
@@ -63,14 +63,14 @@ pub fn render() {
     get_s_val!(IMG_ARR_RIGHT).put_on_canvas(set_pixel, 37, 94);
     rectfill(22, 93, 13, 7, 15);
     print(
-        &format!("{}", unsafe { pad_start(CURSPR.to_string(), '0', 3) }),
+        &unsafe { pad_start(CURSPR.to_string(), '0', 3) },
         Some(23),
         Some(94),
         None,
     );
 
     // tilesheet
-    print(&"tilesheet".to_string(), Some(1), Some(102), None);
+    print("tilesheet", Some(1), Some(102), None);
     rectfill(0, 108, 129, 1, 15);
     rectfill(0, 108, 1, 65, 15);
     rectfill(129, 108, 1, 65, 15);
@@ -120,7 +120,7 @@ pub fn render() {
 fn change_selected(value: i32) {
     let mut s = unsafe { CURSPR };
     if value < 0 {
-        let val = value.abs() as u32;
+        let val = value.unsigned_abs();
         if s >= val {
             s -= val;
         } else {
@@ -129,7 +129,7 @@ fn change_selected(value: i32) {
             s -= val;
         }
     } else {
-        let val = value.abs() as u32;
+        let val = value.unsigned_abs();
         if 255 - s >= val {
             s += val;
         } else {
@@ -161,7 +161,7 @@ static MAXSPR: u32 = 256;
 c_singleton!(IMG, &'static mut Vec<u8>, get_image_vec);
 
 pub fn mousedown(button: MouseButton, mut x: u32, mut y: u32) {
-    if x >= 9 && x <= 49 && y >= 43 && y <= 83 && button == MouseButton::Left {
+    if (9..=49).contains(&x) && (43..=83).contains(&y) && button == MouseButton::Left {
         x -= 9;
         y -= 43;
         x /= 10;
@@ -171,7 +171,7 @@ pub fn mousedown(button: MouseButton, mut x: u32, mut y: u32) {
         }
     }
 
-    if x >= 71 && x < 153 && y >= 22 && y < 104 && button == MouseButton::Left {
+    if (71..153).contains(&x) && (22..104).contains(&y) && button == MouseButton::Left {
         x -= 71;
         y -= 22;
         x /= 10;
@@ -181,12 +181,12 @@ pub fn mousedown(button: MouseButton, mut x: u32, mut y: u32) {
                 CURX = x as u8;
                 CURY = y as u8;
                 let (ox, oy) = get_offset();
-                get_s_val!(IMG)[((CURY as u32 + oy) * 128 + CURX as u32 + ox) as usize] =
+                IMG.get()[((CURY as u32 + oy) * 128 + CURX as u32 + ox) as usize] =
                     SELECTED % 16;
             }
         }
     }
-    if x >= 71 && x < 153 && y >= 22 && y < 104 && button == MouseButton::Right {
+    if (71..153).contains(&x) && (22..104).contains(&y) && button == MouseButton::Right {
         x -= 71;
         y -= 22;
         x /= 10;
@@ -196,14 +196,13 @@ pub fn mousedown(button: MouseButton, mut x: u32, mut y: u32) {
                 CURX = x as u8;
                 CURY = y as u8;
                 let (ox, oy) = get_offset();
-                SELECTED = get_s_val!(IMG)[((CURY as u32 + oy) * 128 + CURX as u32 + ox) as usize];
+                SELECTED = IMG.get()[((CURY as u32 + oy) * 128 + CURX as u32 + ox) as usize];
             }
         }
     }
     if button == MouseButton::Left
-        && y >= 94
-        && y <= 99
-        && ((x >= 15 && x <= 19) || (x >= 37 && x <= 42))
+        && (94..=99).contains(&y)
+        && ((15..=19).contains(&x) || (37..=42).contains(&x))
     {
         let mut _s = unsafe { CURSPR };
         if x < 37 {
@@ -222,7 +221,7 @@ pub fn mousedown(button: MouseButton, mut x: u32, mut y: u32) {
         }
         unsafe { CURSPR = _s };
     }
-    if button == MouseButton::Left && y >= 110 && y <= 125 && x >= 130 && x <= 138 {
+    if button == MouseButton::Left && (110..=125).contains(&y) && (130..=138).contains(&x) {
         if y > 118 {
             unsafe {
                 CURSPR = MAXSPR / 2;
@@ -233,7 +232,7 @@ pub fn mousedown(button: MouseButton, mut x: u32, mut y: u32) {
             }
         }
     }
-    if button == MouseButton::Left && y >= 109 && y < 173 && x >= 1 && x < 129 {
+    if button == MouseButton::Left && (109..173).contains(&y) && (1..129).contains(&x) {
         unsafe {
             CURSPR = (y - 109 + (if CURSPR < MAXSPR / 2 { 0 } else { 64 })) / 8 * 16 + (x - 1) / 8
         }
@@ -241,13 +240,13 @@ pub fn mousedown(button: MouseButton, mut x: u32, mut y: u32) {
 }
 
 pub fn mousemove(x: u32, y: u32) {
-    if x >= 71 && x < 153 && y >= 22 && y < 104 && mouse_button_down(MouseButton::Left) {
+    if (71..153).contains(&x) && (22..104).contains(&y) && mouse_button_down(MouseButton::Left) {
         mousedown(MouseButton::Left, x, y);
     }
-    if x >= 71 && x < 153 && y >= 22 && y < 104 && mouse_button_down(MouseButton::Right) {
+    if (71..153).contains(&x) && (22..104).contains(&y) && mouse_button_down(MouseButton::Right) {
         mousedown(MouseButton::Right, x, y);
     }
-    if mouse_button_down(MouseButton::Left) && y >= 109 && y < 173 && x >= 1 && x < 129 {
+    if mouse_button_down(MouseButton::Left) && (109..173).contains(&y) && (1..129).contains(&x) {
         mousedown(MouseButton::Left, x, y);
     }
 }
\ No newline at end of file
@@ -1,15 +1,11 @@
 use crate::charmap::put_char_on_canvas_custom;
-use highlighter::core::language::Language;
-use highlighter::core::language::Scope;
-use highlighter::core::Error;
-use highlighter::highlight;
+use crate::luastd::BUILTINS;
+use regex::{Error, Regex};
 
 use super::canvas_functions::rectfill;
 use super::canvas_functions::set_pixel;
 use super::editor::SelectionCoordinate;
 
-pub struct R16Lang;
-
 #[derive(Clone, Copy)]
 pub enum SyntaxColor {
     Fg = 12,
@@ -31,82 +27,84 @@ pub fn vec_to_regex(vec: Vec<&str>) -> String {
     format!("\\b({})\\b", vec.join("|"))
 }
 
-impl Language for R16Lang {
-    fn name(&self) -> String {
-        "r16".to_string()
-    }
-
-    fn init(
-        &self,
-        x: &mut highlighter::core::LexerContext,
-    ) -> Result<(), highlighter::core::Error> {
-        // TODO: Fix dis
-        x.token(Scope::Comment, "--\\[\\[([^]]*\\]\\])")?;
-        x.token(Scope::Comment, "--([^\n]*)")?;
-        x.token(
-            Scope::StringQuoted,
-            "(\"[^\"\\\\\\n]*(?:\\\\.[^\"\\\\]*)*\")",
-        )?;
-        x.token(Scope::StringQuoted, "\\[\\[([^]]*)\\]\\]")?;
-        x.token(Scope::ConstantNumber, "\\b([0-9][0-9.]*)\\b")?;
-        x.token(Scope::ConstantNumber, "\\b(0x[0-9a-f]+)\\b")?;
-        x.token(Scope::ConstantNumber, "\\b(0b[0-1]+)\\b")?;
-        x.token(
-            Scope::KeywordControl,
+// earlier rules win when two of them match at the same position
+fn rules() -> Result<Vec<(SyntaxColor, Regex)>, Error> {
+    let rules = vec![
+        (SyntaxColor::Comment, "--\\[\\[([^]]*\\]\\])".to_string()),
+        (SyntaxColor::Comment, "--([^\n]*)".to_string()),
+        (
+            SyntaxColor::String,
+            "(\"[^\"\\\\\\n]*(?:\\\\.[^\"\\\\]*)*\")".to_string(),
+        ),
+        (SyntaxColor::String, "\\[\\[([^]]*)\\]\\]".to_string()),
+        (SyntaxColor::Number, "\\b([0-9][0-9.]*)\\b".to_string()),
+        (SyntaxColor::Number, "\\b(0x[0-9a-f]+)\\b".to_string()),
+        (SyntaxColor::Number, "\\b(0b[0-1]+)\\b".to_string()),
+        (
+            SyntaxColor::Keyword,
             vec_to_regex(vec![
                 "if", "ifelse", "else", "end", "do", "then", "while", "for", "break", "goto",
                 "and", "or", "not", "local", "return", "continue", "until", "function",
             ]),
-        )?;
-        x.token(
-            Scope::ConstantLanguage,
-            vec_to_regex(vec!["true", "false", "nil"]),
-        )?;
-        x.token(
-            Scope::KeywordOperator,
+        ),
+        (SyntaxColor::Const, vec_to_regex(vec!["true", "false", "nil"])),
+        (
+            SyntaxColor::Operator,
             vec![
                 "==", "=", "~=", "\\|", ">>", "<<", "&", "\\+", "-", "\\*", "/", "<=", ">=", "<",
                 ">",
             ]
             .join("|"),
-        )?;
-        x.token(
-            Scope::NameFunction,
+        ),
+        (
+            SyntaxColor::Builtin,
             vec_to_regex(vec!["_init", "_update", "_draw"]),
-        )?;
-        x.token(
-            Scope::NameFunction,
-            vec_to_regex(vec![
-                "sleep", "add", "stop", "Exit", "peek", "poke", "btn", "btnp", "setp", "cls",
-                "rectfill", "cursor", "print", "rect", "ellipse", "circle", "line", "camera",
-                "pal", "palt", "setpal", "sspr", "spr", "rnd", "time", "cos", "sin", "sqrt", "flr",
-                "sfx", "del",
-            ]),
-        )?;
-
-        Ok(())
-    }
+        ),
+        (
+            SyntaxColor::Builtin,
+            vec_to_regex(BUILTINS.iter().map(|(name, _)| *name).collect()),
+        ),
+    ];
+
+    rules
+        .into_iter()
+        .map(|(color, re)| Ok((color, Regex::new(&re)?)))
+        .collect()
 }
 
 pub fn highlight_code(code: String) -> Result<Vec<SyntaxToken>, Error> {
     let mut vec: Vec<SyntaxToken> = Vec::new();
+    let rules = rules()?;
+
+    let mut pos = 0;
+    while pos < code.len() {
+        // the rule whose match starts first
+        let mut next: Option<(usize, usize, SyntaxColor)> = None;
+        for (color, re) in &rules {
+            if let Some(m) = re.find_at(&code, pos) {
+                if next.is_none_or(|(start, _, _)| m.start() < start) {
+                    next = Some((m.start(), m.end(), *color));
+                }
+            }
+        }
 
-    let parsed = highlight(R16Lang, &code)?;
-
-    for t in parsed {
-        vec.push(SyntaxToken {
-            str: t.value,
-            color: match t.scope {
-                Scope::StringQuoted => SyntaxColor::String,
-                Scope::ConstantNumber => SyntaxColor::Number,
-                Scope::KeywordControl => SyntaxColor::Keyword,
-                Scope::ConstantLanguage => SyntaxColor::Const,
-                Scope::KeywordOperator => SyntaxColor::Operator,
-                Scope::Comment => SyntaxColor::Comment,
-                Scope::NameFunction => SyntaxColor::Builtin,
-                _ => SyntaxColor::Fg,
-            },
-        })
+        let (start, end, color) = match next {
+            Some(n) => n,
+            None => (code.len(), code.len(), SyntaxColor::Fg),
+        };
+        if start > pos {
+            vec.push(SyntaxToken {
+                str: code[pos..start].to_string(),
+                color: SyntaxColor::Fg,
+            });
+        }
+        if end > start {
+            vec.push(SyntaxToken {
+                str: code[start..end].to_string(),
+                color,
+            });
+        }
+        pos = end;
     }
 
     Ok(vec)
@@ -115,14 +113,8 @@ pub fn highlight_code(code: String) -> Result<Vec<SyntaxToken>, Error> {
 fn is_in_selection(x: i32, y: i32, start: &SelectionCoordinate, end: &SelectionCoordinate) -> bool {
     let mut _start = start;
     let mut _end = end;
-    if start.line > end.line {
-        let tmp = start;
-        _start = end;
-        _end = tmp;
-    } else if start.line == end.line && end.col < start.col {
-        let tmp = start;
-        _start = end;
-        _end = tmp;
+    if start.line > end.line || (start.line == end.line && end.col < start.col) {
+        std::mem::swap(&mut _start, &mut _end);
     }
 
     if x < 0 || y < 0 {
@@ -131,15 +123,10 @@ fn is_in_selection(x: i32, y: i32, start: &SelectionCoordinate, end: &SelectionC
     let y = y as u32;
     let x = x as u32;
 
-    if y < _start.line || y > _end.line {
-        return false;
-    } else if y == _start.line && x < _start.col {
-        return false;
-    } else if y == _end.line && x >= _end.col {
-        return false;
-    }
-
-    true
+    !(y < _start.line
+        || y > _end.line
+        || (y == _start.line && x < _start.col)
+        || (y == _end.line && x >= _end.col))
 }
 
 pub fn print_highlighted_code(
@@ -156,14 +143,13 @@ pub fn print_highlighted_code(
         selection_start.col != selection_end.col || selection_start.line != selection_end.line;
 
     for t in code {
-        let bytes: Vec<char> = t.str.chars().collect();
-        for i in 0..bytes.len() {
+        for char in t.str.chars() {
             if has_selection
                 && is_in_selection((x - ox) / 4, (y - oy) / 6, selection_start, selection_end)
             {
                 rectfill(x, y, 4, 6, 7);
             }
-            match bytes[i] {
+            match char {
                 // \n
                 '\n' => {
                     y += 6;
@@ -172,9 +158,59 @@ pub fn print_highlighted_code(
                 // ' '
                 ' ' => x += 4,
                 _ => {
-                    x += put_char_on_canvas_custom(bytes[i], x, y, t.color as u8, set_pixel) as i32;
+                    x += put_char_on_canvas_custom(char, x, y, t.color as u8, set_pixel) as i32;
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{highlight_code, SyntaxColor};
+
+    fn colors(code: &str) -> Vec<(String, u8)> {
+        highlight_code(code.to_string())
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.str, t.color as u8))
+            .collect()
+    }
+
+    #[test]
+    fn tokens_cover_the_whole_code() {
+        let code = "function _draw()\n  cls(0) -- clear\n  x = \"a\" .. 0x1f\nend";
+        let tokens = highlight_code(code.to_string()).unwrap();
+        assert_eq!(tokens.iter().map(|t| t.str.as_str()).collect::<String>(), code);
+    }
+
+    #[test]
+    fn tokens_get_their_colors() {
+        assert_eq!(
+            colors("if x == nil then cls() end"),
+            vec![
+                ("if".to_string(), SyntaxColor::Keyword as u8),
+                (" x ".to_string(), SyntaxColor::Fg as u8),
+                ("==".to_string(), SyntaxColor::Operator as u8),
+                (" ".to_string(), SyntaxColor::Fg as u8),
+                ("nil".to_string(), SyntaxColor::Const as u8),
+                (" ".to_string(), SyntaxColor::Fg as u8),
+                ("then".to_string(), SyntaxColor::Keyword as u8),
+                (" ".to_string(), SyntaxColor::Fg as u8),
+                ("cls".to_string(), SyntaxColor::Builtin as u8),
+                ("() ".to_string(), SyntaxColor::Fg as u8),
+                ("end".to_string(), SyntaxColor::Keyword as u8),
+            ]
+        );
+        assert_eq!(
+            colors("-- if \"x\"\ns = \"-- no\""),
+            vec![
+                ("-- if \"x\"".to_string(), SyntaxColor::Comment as u8),
+                ("\ns ".to_string(), SyntaxColor::Fg as u8),
+                ("=".to_string(), SyntaxColor::Operator as u8),
+                (" ".to_string(), SyntaxColor::Fg as u8),
+                ("\"-- no\"".to_string(), SyntaxColor::String as u8),
+            ]
+        );
+    }
+}
@@ -7,23 +7,26 @@ use std::process::Command;
 use std::{io::Write, path::Path};
 
 use crate::{
+    budget::{exceeded_limits, get_limits, refresh as refresh_usage, set_limits},
     c_singleton,
     canvas_functions::PALETTE1,
     custom_canvas_functions::print as c_print,
     gamestate::{
-        gamedata_to_string, get_code, get_path, get_preview_image, load_code, load_game, run_game,
+        gamedata_to_string, get_code, get_path, get_preview_colors, get_preview_image, load_code, load_game, run_game,
         set_file_name,
     },
     get_s_val,
     info::VERSION,
-    luautils::print_err,
     screenshot_saver::write as write_png,
     sprites::CARTRIDGE,
     utils::{is_alt_pressed, is_ctrl_pressed},
     Singleton, CARTSPATH,
 };
 
-use super::{canvas_functions::*, key_utils::keycode_to_character, overlay::set_overlay};
+use super::{
+    canvas_functions::*, error_screen::show_error, key_utils::keycode_to_character,
+    message::set_message, overlay::set_overlay,
+};
 use std::{
     collections::VecDeque,
     time::{SystemTime, UNIX_EPOCH},
@@ -39,7 +42,7 @@ static C_HT: char = '\t';
 
 unsafe fn __add_line_to_stdout<T: Into<String>>(line: T) {
     let l = line.into();
-    if l.find(|c| c == C_LF || c == C_CR || c == C_HT).is_some() {
+    if l.find([C_LF, C_CR, C_HT]).is_some() {
         return;
     }
     while STDOUT.len() >= 29 {
@@ -50,8 +53,7 @@ unsafe fn __add_line_to_stdout<T: Into<String>>(line: T) {
 
 pub fn add_line_to_stdout<T: Into<String>>(line: T) {
     line.into()
-        .replace('\r', "")
-        .replace('\t', "")
+        .replace(['\r', '\t'], "")
         .split('\n')
         .for_each(|line| unsafe { __add_line_to_stdout(line) });
 }
@@ -62,9 +64,9 @@ pub fn add_char_to_stdin(char: char) {
             __add_line_to_stdout(STDIN.clone());
             let s = &STDIN.as_str();
             HISTORY.push(s.to_string());
-            run_command(&s[1..].trim());
+            run_command(s[1..].trim());
             STDIN.clear();
-            STDIN.push_str(">")
+            STDIN.push('>')
         }
 
         return;
@@ -74,29 +76,29 @@ pub fn add_char_to_stdin(char: char) {
     }
 }
 
-c_singleton!(CWD, Vec<String>, || Vec::new());
+c_singleton!(CWD, Vec<String>, Vec::new);
 
 pub fn run_command(mut cmd: &str) {
     cmd = cmd.trim();
-    if cmd.len() < 1 {
+    if cmd.is_empty() {
         return;
     }
     let mut args: Vec<&str> = Vec::new();
     let mut __args = cmd.split(' ');
     let mut cmd = "";
-    while let Some(arg) = __args.next() {
-        if cmd.len() < 1 {
+    for arg in __args {
+        if cmd.is_empty() {
             cmd = arg;
         } else {
             args.push(arg);
         }
     }
-    if cmd.len() < 1 {
+    if cmd.is_empty() {
         return;
     }
     match cmd {
         "pwd" => {
-            if get_s_val!(CWD).len() < 1 {
+            if get_s_val!(CWD).is_empty() {
                 add_line_to_stdout("~");
             } else {
                 add_line_to_stdout("~/".to_string() + &get_s_val!(CWD).join("/"));
@@ -104,14 +106,14 @@ pub fn run_command(mut cmd: &str) {
         }
         "mkdir" => {
             let joined = args.join("");
-            if let Err(..) = create_dir(&get_s_val!(CARTSPATH).join(joined.as_str())) {
+            if create_dir(&get_s_val!(CARTSPATH).join(joined.as_str())).is_err() {
                 add_line_to_stdout("could not create directory");
             }
         }
         "ls" => match read_dir(&get_s_val!(CARTSPATH).join(get_s_val!(CWD).join("/"))) {
             None => add_line_to_stdout("Failed to read directory".to_string()),
             Some(entries) => {
-                if get_s_val!(CWD).len() < 1 {
+                if get_s_val!(CWD).is_empty() {
                     add_line_to_stdout("~: ");
                 } else {
                     add_line_to_stdout("~/".to_string() + &get_s_val!(CWD).join("/") + ":");
@@ -132,17 +134,17 @@ pub fn run_command(mut cmd: &str) {
             } else {
                 remove_file(&p)
             };
-            if let Err(..) = res {
+            if res.is_err() {
                 add_line_to_stdout("could not remove the file".to_string());
             }
         }
         "new" => {
-            if args.len() < 1 {
+            if args.is_empty() {
                 load_code(NEW_STR.to_string(), None);
                 set_overlay(super::OverlayType::CodeEditor);
             } else {
                 let name = args[0].trim();
-                if name.len() > 0 {
+                if !name.is_empty() {
                     let name = name.to_owned();
                     if let Some(p) = get_s_val!(CARTSPATH)
                         .join(if name.ends_with(".r16") || name.ends_with(".r16.png") {
@@ -169,7 +171,7 @@ pub fn run_command(mut cmd: &str) {
         "cd" => {
             let joined = args.join(" ");
             let cwd = get_s_val!(CWD);
-            if joined.len() < 1 {
+            if joined.is_empty() {
                 cwd.clear();
             } else {
                 let newpath = joined.split("/");
@@ -178,7 +180,7 @@ pub fn run_command(mut cmd: &str) {
                         cwd.pop();
                     } else if path == "~" {
                         cwd.clear();
-                    } else if path.len() > 0 {
+                    } else if !path.is_empty() {
                         get_s_val!(CWD).push(path.to_string());
                     }
                 }
@@ -189,7 +191,7 @@ pub fn run_command(mut cmd: &str) {
         "save" => save(args),
         "load" => {
             let name = args[0].trim();
-            if name.len() < 1 {
+            if name.is_empty() {
                 return;
             }
             let name = name.to_owned();
@@ -215,7 +217,7 @@ pub fn run_command(mut cmd: &str) {
         "explore" => set_overlay(super::OverlayType::Explore),
         "run" => {
             if let Some(err) = run_game() {
-                add_line_to_stdout(print_err(err));
+                show_error(err);
             }
         }
         "version" => {
@@ -234,10 +236,17 @@ pub fn run_command(mut cmd: &str) {
                 println!("failed to open the folder: {}", err);
             }
         }
+        "limits" => limits(args),
         "export" => {
             let mut filename = args.join(" ");
             let img = get_preview_image();
-            if filename.len() < 1 {
+            let exceeded = exceeded_limits(&refresh_usage());
+            if !exceeded.is_empty() {
+                add_line_to_stdout("can't export, the cart exceeds its limits:");
+                for msg in exceeded {
+                    add_line_to_stdout(msg);
+                }
+            } else if filename.is_empty() {
                 add_line_to_stdout("you have to give the image a name!");
             } else if img.is_none() {
                 add_line_to_stdout("no preview image!");
@@ -256,12 +265,12 @@ pub fn run_command(mut cmd: &str) {
 
                         for i in tmp {
                             unsafe {
-                                BYTES.push(i.clone());
+                                BYTES.push(*i);
                             }
                         }
 
                         let lines = get_code();
-                        let line1 = if lines.len() > 0 && lines[0].len() > 2 {
+                        let line1 = if !lines.is_empty() && lines[0].len() > 2 {
                             &lines[0][2..]
                         } else {
                             ""
@@ -294,10 +303,15 @@ pub fn run_command(mut cmd: &str) {
                             37,
                         );
 
+                        // the preview is shown with the colors of the screen, the label around it with the builtin ones
+                        let preview_colors = get_preview_colors().unwrap_or(PALETTE1);
                         for y in 0..300 {
                             for x in 0..250 {
-                                let col = unsafe {
-                                    PALETTE1[BYTES[(299 - y) * 250 + x].clone() as usize]
+                                let index = unsafe { BYTES[(299 - y) * 250 + x] as usize };
+                                let col = if (24..224).contains(&x) && 299 - y >= 37 && 299 - y < 217 {
+                                    preview_colors[index % 16]
+                                } else {
+                                    PALETTE1[index]
                                 };
                                 let (r, g, b) = col.get_values();
                                 vec.push(r);
@@ -318,13 +332,13 @@ pub fn run_command(mut cmd: &str) {
 
                         match write_png(&mut f, &vec, 250, 300) {
                             Ok(..) => {
-                                if let Err(e) = f.write(&bytes) {
-                                    add_line_to_stdout(&format!("{}", e).to_lowercase());
+                                if let Err(e) = f.write(bytes) {
+                                    add_line_to_stdout(format!("{}", e).to_lowercase());
                                 } else {
                                     add_line_to_stdout("written successfully!");
                                 }
                             }
-                            Err(e) => add_line_to_stdout(&format!("{:?}", e).to_lowercase()),
+                            Err(e) => add_line_to_stdout(format!("{:?}", e).to_lowercase()),
                         }
                     }
                 }
@@ -334,16 +348,52 @@ pub fn run_command(mut cmd: &str) {
     };
 }
 
+// limits: shows the usage of the cart. limits <tokens|chars|size|instructions|memory> <n>: changes a limit
+fn limits(args: Vec<&str>) {
+    let mut limits = get_limits();
+    if args.len() >= 2 {
+        let value = match args[1].trim().parse::<usize>() {
+            Ok(value) => value,
+            Err(..) => return add_line_to_stdout(format!("not a number: {}", args[1])),
+        };
+        match args[0].trim() {
+            "tokens" => limits.tokens = value,
+            "chars" => limits.chars = value,
+            "size" => limits.size = value,
+            "instructions" => limits.instructions = value,
+            "memory" => limits.memory = value,
+            name => return add_line_to_stdout(format!("unknown limit: {}", name)),
+        }
+        set_limits(limits);
+    } else if args.len() == 1 {
+        return add_line_to_stdout("use limits <tokens|chars|size|instructions|memory> <n>");
+    }
+
+    let usage = refresh_usage();
+    add_line_to_stdout(format!("tokens: {}/{}", usage.tokens, limits.tokens));
+    add_line_to_stdout(format!("chars:  {}/{}", usage.chars, limits.chars));
+    add_line_to_stdout(format!("size:   {}/{} bytes", usage.size, limits.size));
+    add_line_to_stdout(format!("instructions per frame: {}", limits.instructions));
+    add_line_to_stdout(format!("lua memory: {} bytes", limits.memory));
+}
+
+fn warn_exceeded_limits() {
+    if let Some(msg) = exceeded_limits(&refresh_usage()).first() {
+        set_message(&format!("saved, but {}", msg));
+    }
+}
+
 pub fn save(args: Vec<&str>) {
-    if args.len() < 1 {
+    if args.is_empty() {
         if let Some(path) = get_path() {
-            if let Err(..) = write(
+            if write(
                 &Path::new(&path).to_path_buf(),
                 gamedata_to_string().as_bytes(),
-            ) {
+            ).is_err() {
                 add_line_to_stdout("failed to save");
             } else {
                 set_overlay(super::OverlayType::CodeEditor);
+                warn_exceeded_limits();
             }
         } else {
             add_line_to_stdout("use save <path>")
@@ -354,11 +404,12 @@ pub fn save(args: Vec<&str>) {
             path.push(p);
         }
         path.push(args.join(" ") + ".r16");
-        set_file_name(path.to_str().and_then(|f| Some(f.to_string())));
-        if let Some(..) =
-            open_file(&path).and_then(|mut f| f.write(gamedata_to_string().as_bytes()).ok())
-        {
+        set_file_name(path.to_str().map(|f| f.to_string()));
+        let saved =
+            open_file(&path).and_then(|mut f| f.write(gamedata_to_string().as_bytes()).ok());
+        if saved.is_some() {
             set_overlay(super::OverlayType::CodeEditor);
+            warn_exceeded_limits();
         } else {
             add_line_to_stdout("failed to save");
         }
@@ -394,7 +445,7 @@ pub fn update() {}
 pub fn init() {
     unsafe {
         STDIN.clear();
-        STDIN.push_str(">");
+        STDIN.push('>');
     }
 }
 
@@ -404,7 +455,7 @@ pub fn handle_key(key: Keycode) {
         return;
     }
     if is_ctrl_pressed() && !is_alt_pressed() {
-        if match key {
+        let handled = match key {
             Keycode::L => {
                 unsafe { STDOUT.clear() }
                 true
@@ -417,7 +468,8 @@ pub fn handle_key(key: Keycode) {
                 true
             }
             _ => false,
-        } {
+        };
+        if handled {
             return;
         }
     }
@@ -426,10 +478,8 @@ pub fn handle_key(key: Keycode) {
     }
     unsafe {
         match key {
-            Keycode::Backspace => {
-                if STDIN.len() > 1 {
-                    STDIN.pop();
-                }
+            Keycode::Backspace if STDIN.len() > 1 => {
+                STDIN.pop();
             }
             Keycode::Up => {
                 if let Some(h) = HISTORY.pop() {
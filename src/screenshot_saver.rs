@@ -3,18 +3,23 @@ use std::fs::File;
 use chrono::{DateTime, Utc};
 
 use crate::{
-    canvas_functions::PALETTE1, gamestate::save_screenshot, get_s_val, memory::displaymemory,
+    canvas_functions::{color_index_to_color, screen_height, screen_width},
+    gamestate::save_screenshot,
+    get_s_val,
+    memory::{displaymemory, DISPLAY_OFFSET},
     SCREENSHOTSPATH,
 };
 
 pub fn screenshot() {
     save_screenshot();
-    let mut img: Vec<u8> = Vec::with_capacity(200 * 180 * 4);
+    // the screenshot has the resolution of the screen mode
+    let (width, height) = (screen_width(), screen_height());
+    let mut img: Vec<u8> = Vec::with_capacity((width * height * 4) as usize);
 
     let mem = get_s_val!(displaymemory);
-    let palette = PALETTE1;
-    for i in 0..200 * 180 {
-        let color = palette[mem.get_at_addr_d(i + 0x13) as usize];
+    for i in 0..width * height {
+        // the colors as they are on the screen
+        let color = color_index_to_color(mem.get_at_addr_d(i + DISPLAY_OFFSET));
 
         let (r, g, b) = color.get_values();
         img.push(r);
@@ -28,10 +33,9 @@ pub fn screenshot() {
     let file_name = now.format("%F;%T.png").to_string();
 
     let file = File::create(get_s_val!(SCREENSHOTSPATH).join(file_name));
-    let _file = file.and_then(|mut f| write(&mut f, &img, 200, 180));
-    match _file {
-        Err(e) => println!("Failed to write screenshot: {:?}", e),
-        Ok(..) => {}
+    let _file = file.and_then(|mut f| write(&mut f, &img, width, height));
+    if let Err(e) = _file {
+        println!("Failed to write screenshot: {:?}", e);
     }
 }
 
@@ -62,7 +66,7 @@ mod crc32 {
                 }
                 c.table[i] = v;
             }
-            return c;
+            c
         }
 
         pub fn start(&mut self) {
@@ -117,7 +121,7 @@ mod adler32 {
         }
 
         pub fn finalize(&self) -> u32 {
-            return (self.b << 16) | self.a;
+            (self.b << 16) | self.a
         }
 
         #[allow(dead_code)]
@@ -150,7 +154,7 @@ mod fake_zlib {
             (5 * {
                 let n = data.len() / CHUNK_SIZE;
                 // include an extra chunk when we don't fit exactly into CHUNK_SIZE
-                n + {if data.len() == n * CHUNK_SIZE && data.len() != 0 { 0 } else { 1 }}
+                n + {if data.len() == n * CHUNK_SIZE && !data.is_empty() { 0 } else { 1 }}
             }) +
             // data
             data.len() +
@@ -190,7 +194,7 @@ mod fake_zlib {
         raw_data.extend(&u32_to_u8_be(crc.finalize()));
 
         assert_eq!(final_len, raw_data.len());
-        return raw_data;
+        raw_data
     }
 }
 
@@ -210,20 +214,20 @@ pub fn write<W: ::std::io::Write>(
         png_tag: &[u8; 4],
         data: &[u8],
     ) -> Result<(), ::std::io::Error> {
-        file.write(&u32_to_u8_be(data.len() as u32))?;
-        file.write(png_tag)?;
-        file.write(data)?;
+        file.write_all(&u32_to_u8_be(data.len() as u32))?;
+        file.write_all(png_tag)?;
+        file.write_all(data)?;
         {
             let mut crc = crc32::Crc32::new();
             crc.start();
             crc.update(png_tag);
             crc.update(data);
-            file.write(&u32_to_u8_be(crc.finalize()))?;
+            file.write_all(&u32_to_u8_be(crc.finalize()))?;
         }
         Ok(())
     }
 
-    file.write(b"\x89PNG\r\n\x1a\n")?;
+    file.write_all(b"\x89PNG\r\n\x1a\n")?;
     {
         let wb = u32_to_u8_be(w);
         let hb = u32_to_u8_be(h);
@@ -1,9 +1,17 @@
 // #[cfg(any(target_family = "unix", target_family = "windows"))]
+#[cfg(feature = "sdl2")]
 use crate::systems::sdl2::{
     copy_to_clipboard as sdl2_copy_to_clipboard, get_size as sdl2_get_size, init as sdl2_init,
     read_clipboard as sdl2_read_clipboard, show_cursor as sdl2_show_cursor,
 };
 
+#[cfg(not(feature = "sdl2"))]
+use crate::systems::headless::{
+    copy_to_clipboard as headless_copy_to_clipboard, get_size as headless_get_size,
+    init as headless_init, read_clipboard as headless_read_clipboard,
+    show_cursor as headless_show_cursor,
+};
+
 // #[cfg(target_family = "wasm")]
 // use crate::systems::wasm::{
 //     copy_to_clipboard as wasm_copy_to_clipboard, get_size as wasm_get_size, init as wasm_init,
@@ -99,28 +107,40 @@ pub enum Event {
 
 pub fn get_size() -> (u32, u32) {
     // #[cfg(any(target_family = "unix", target_family = "windows"))]
+    #[cfg(feature = "sdl2")]
     return sdl2_get_size();
+    #[cfg(not(feature = "sdl2"))]
+    return headless_get_size();
     // #[cfg(target_family = "wasm")]
     // return wasm_get_size();
 }
 
 pub fn show_cursor(value: bool) {
     // #[cfg(any(target_family = "unix", target_family = "windows"))]
+    #[cfg(feature = "sdl2")]
     sdl2_show_cursor(value);
+    #[cfg(not(feature = "sdl2"))]
+    headless_show_cursor(value);
     // #[cfg(target_family = "wasm")]
     // wasm_show_cursor(value);
 }
 
 pub fn init() {
     // #[cfg(any(target_family = "unix", target_family = "windows"))]
+    #[cfg(feature = "sdl2")]
     sdl2_init();
+    #[cfg(not(feature = "sdl2"))]
+    headless_init();
     // #[cfg(target_family = "wasm")]
     // wasm_init();
 }
 
 pub fn read_clipboard() -> String {
     // #[cfg(any(target_family = "unix", target_family = "windows"))]
+    #[cfg(feature = "sdl2")]
     return sdl2_read_clipboard();
+    #[cfg(not(feature = "sdl2"))]
+    return headless_read_clipboard();
     // #[cfg(target_family = "wasm")]
     // return wasm_read_clipboard();
 }
@@ -129,7 +149,10 @@ pub fn copy_to_clipboard(str: &str) {
     // #[cfg(target_family = "wasm")]
     // wasm_copy_to_clipboard(str);
     // #[cfg(any(target_family = "unix", target_family = "windows"))]
+    #[cfg(feature = "sdl2")]
     sdl2_copy_to_clipboard(str);
+    #[cfg(not(feature = "sdl2"))]
+    headless_copy_to_clipboard(str);
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,7 +26,7 @@ pub fn read_dir(path: &PathBuf) -> Option<Vec<DirEntry>> {
         Ok(entries) => {
             let mut vec: Vec<DirEntry> = Vec::new();
 
-            for e in entries.filter(|el| el.is_ok()).map(|el| el.unwrap()) {
+            for e in entries.flatten() {
                 if let Some(name) = e.file_name().to_str().map(|str| str.to_string()) {
                     if let Ok(typ) = e.file_type() {
                         let is_dir = typ.is_dir();
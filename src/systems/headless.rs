@@ -0,0 +1,23 @@
+// a backend without a window or audio, used when the sdl2 feature is off.
+// `cargo test --no-default-features` runs the tests without linking SDL2
+use crate::{c_singleton, get_s_val, set_s_val, Singleton, HEIGHT, WIDTH};
+
+c_singleton!(CLIPBOARD, String, String::new);
+
+pub fn get_size() -> (u32, u32) {
+    (WIDTH, HEIGHT)
+}
+
+pub fn init() {
+    eprintln!("built without the sdl2 feature, there is no window to open");
+}
+
+pub fn show_cursor(_value: bool) {}
+
+pub fn read_clipboard() -> String {
+    get_s_val!(CLIPBOARD).clone()
+}
+
+pub fn copy_to_clipboard(str: &str) {
+    set_s_val!(CLIPBOARD, str.to_string());
+}
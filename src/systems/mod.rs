@@ -1,5 +1,8 @@
 // #[cfg(any(target_family="unix", target_family="windows"))]
+#[cfg(feature = "sdl2")]
 pub mod sdl2;
+#[cfg(not(feature = "sdl2"))]
+pub mod headless;
 // #[cfg(any(target_family="unix", target_family="windows"))]
 pub mod fs;
 // #[cfg(any(target_family="unix", target_family="windows"))]
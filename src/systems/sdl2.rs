@@ -151,9 +151,9 @@ pub fn init() {
         texture
             .update(None, &vec, (WIDTH * 4) as usize)
             .err()
-            .and_then(|e| {
+            .map(|e| {
                 eprintln!("Error: Could not update the texture: {}", e);
-                Some(e)
+                e
             });
 
         windowcanvas.set_draw_color(BLACK);